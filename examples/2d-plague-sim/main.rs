@@ -4,6 +4,8 @@ use functionality::{config::load_config_data, point::Point2D, population_types::
 use macroquad::{miniquad::window::set_window_size, prelude::*};
 use simulation::Simulation;
 mod simulation;
+#[cfg(test)]
+mod test_support;
 
 #[macroquad::main("Simulation")]
 async fn main() {
@@ -22,24 +24,26 @@ async fn main() {
         // update
         simulation.update();
 
+        let populations = simulation.geography.population_snapshot();
+
         // north america ID: 0
         draw_rectangle(0.0, 0.0, 100.0, 100.0, GREEN);
-        draw_text(format!("{}", simulation.geography.get_region(RegionID(0)).unwrap().population.get_total()).as_str(), 0.0, 100.0, 50.0, BLACK);
+        draw_text(format!("{}", populations[&RegionID(0)].get_total()).as_str(), 0.0, 100.0, 50.0, BLACK);
 
         // brazil: ID 1
         draw_rectangle(0.0, 150.0, 200.0, 100.0, GREEN);
-        draw_text(format!("{}", simulation.geography.get_region(RegionID(1)).unwrap().population.get_total()).as_str(), 0.0, 150.0, 50.0, BLACK);
+        draw_text(format!("{}", populations[&RegionID(1)].get_total()).as_str(), 0.0, 150.0, 50.0, BLACK);
 
         // asia: ID 2
         draw_rectangle(400.0, 0.0, 100.0, 100.0, GREEN);
-        draw_text(format!("{}", simulation.geography.get_region(RegionID(2)).unwrap().population.get_total()).as_str(), 400.0, 100.0, 50.0, BLACK);
+        draw_text(format!("{}", populations[&RegionID(2)].get_total()).as_str(), 400.0, 100.0, 50.0, BLACK);
 
         // africa: ID 3
         draw_rectangle(250.0, 220.0, 100.0, 100.0, GREEN);
-        draw_text(format!("{}", simulation.geography.get_region(RegionID(3)).unwrap().population.get_total()).as_str(), 250.0, 220.0, 50.0, BLACK);
+        draw_text(format!("{}", populations[&RegionID(3)].get_total()).as_str(), 250.0, 220.0, 50.0, BLACK);
 
 
-        for port in simulation.geography.get_ports() {
+        for port in simulation.geography.iter_ports() {
             let x = port.pos.x;
             let y = port.pos.y;
             draw_circle(x as f32, y as f32, 10.0, WHITE);
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+// Items the visualisation in main.rs does not use yet are part of the simulation API exercised by tests, and are marked with allow(dead_code) individually
 
-use functionality::{population_types::{population::Population, PopulationType}, region::{Region, RegionID}, simulation_geography::SimulationGeography, transportation_allocator::{TransportAllocator, TransportJob}};
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, rc::Rc};
+
+use functionality::{math_utils::pick_weighted, pathogen::pathogen_types::pathogen::Pathogen, population_types::{population::Population, PopulationType}, region::{Port, PortID, PortStatus, Region, RegionID}, simulation_geography::SimulationGeography, transportation_allocator::{TransportAllocator, TransportJob}};
 
 
 
@@ -9,122 +11,613 @@ pub struct MediatorStatistics {
     /** Total population currently in transit */
     pub in_transit: Population,
     /** Total population living in regions */
-    pub region_population: Population
+    pub region_population: Population,
+    /** Most infected people observed at once, both in regions and in transit */
+    pub peak_infected: u32,
+    /** Most people observed in transit at once */
+    pub peak_in_transit: u32
 }
 
 impl MediatorStatistics {
     fn new (region_population: Population) -> Self {
-        Self { in_transit: Population::new_healthy(0), region_population }
+        let mut statistics = Self { in_transit: Population::new_healthy(0), region_population, peak_infected: 0, peak_in_transit: 0 };
+        statistics.update_peaks();
+        statistics
+    }
+
+    /** Raises peaks to current values if they exceed them */
+    fn update_peaks(&mut self) {
+        let infected = self.in_transit.infected.saturating_add(self.region_population.infected);
+        let in_transit = u32::try_from(self.in_transit.get_total_u64()).unwrap_or(u32::MAX);
+        self.peak_infected = self.peak_infected.max(infected);
+        self.peak_in_transit = self.peak_in_transit.max(in_transit);
+    }
+
+    /** Moves population that finished travelling from transit to regions */
+    fn record_arrival(&mut self, population: Population) {
+        self.in_transit = self.in_transit.emigrate(population).expect("Arriving population must have been in transit");
+        self.region_population = self.region_population.saturating_add(population);
+    }
+
+    /** Moves population that started travelling from regions to transit */
+    fn record_departure(&mut self, population: Population) {
+        self.region_population = self.region_population.emigrate(population).expect("Departing population must have been in a region");
+        self.in_transit = self.in_transit.saturating_add(population);
+    }
+
+    /** Replaces a region's old population with its new population in region totals */
+    fn record_region_change(&mut self, old_population: Population, new_population: Population) {
+        self.region_population = self.region_population.emigrate(old_population).expect("Changed population must have been in a region").saturating_add(new_population);
+    }
+}
+
+/** Receives notifications about events happening in a simulation */
+pub trait SimulationObserver {
+    /** Called at the end of every update with the update's tick, starting from 0, and every job that finished during it */
+    fn on_tick_end(&mut self, _tick: u32, _arrivals: &[TransportJob]) {}
+}
+
+/** Port status changes scripted to happen on specific ticks */
+#[derive(Default)]
+pub struct ClosureSchedule {
+    events: Vec<(u32, PortID, PortStatus)>
+}
+
+impl ClosureSchedule {
+    /** Schedules port to change to given status on given tick */
+    fn schedule(&mut self, tick: u32, port_id: PortID, status: PortStatus) {
+        self.events.push((tick, port_id, status));
+    }
+
+    /** Changes scheduled for given tick, in order of scheduling */
+    fn events_at(&self, tick: u32) -> impl Iterator<Item = &(u32, PortID, PortStatus)> {
+        self.events.iter().filter(move |(event_tick, _, _)| *event_tick == tick)
+    }
+}
+
+/** Accumulates number of people that have departed from each region to each other region */
+#[derive(Default)]
+pub struct FlowMatrix {
+    flows: HashMap<(RegionID, RegionID), u64>
+}
+
+impl FlowMatrix {
+    /** Records people departing from one region to another */
+    fn record(&mut self, from: RegionID, to: RegionID, population: Population) {
+        let flow = self.flows.entry((from, to)).or_default();
+        *flow = flow.saturating_add(population.get_total_u64());
+    }
+
+    /** Total number of people that have departed from one region to another */
+    #[allow(dead_code)]
+    pub fn flow(&self, from: RegionID, to: RegionID) -> u64 {
+        self.flows.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /** Lists every nonzero flow as a row of from, to, people, ordered by region IDs */
+    #[allow(dead_code)]
+    pub fn to_csv(&self) -> String {
+        let mut entries: Vec<(&(RegionID, RegionID), &u64)> = self.flows.iter().filter(|(_, people)| **people > 0).collect();
+        entries.sort();
+        let mut csv = "from,to,people\n".to_owned();
+        for ((from, to), people) in entries {
+            csv.push_str(&format!("{},{},{}\n", from, to, people));
+        }
+        csv
     }
 }
 
+/** Order in which disease progresses and people travel during each update */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpdateOrder {
+    /** Pathogen acts on regions first, so travelers leave carrying this tick's infections */
+    #[default]
+    DiseaseThenTravel,
+    /** Travelers leave first, so people departing this tick are not affected by the pathogen until they arrive */
+    TravelThenDisease
+}
+
+/** Custom change applied to a region each update, receiving the update's tick */
+pub type RegionRule<P> = Box<dyn Fn(&mut Region<P>, u32)>;
+
+/** Pathogen's step over the whole geography at the end of an update, receiving the update's tick and returning changed regions' populations before and after */
+type PathogenEndTick<P> = Box<dyn Fn(&mut SimulationGeography<P>, u32) -> HashMap<RegionID, (Population, Population)>>;
+
 // Controls transportation interactions between the regions it possesses
 /** Assumes that every port in provided port graph belongs to a region */
 /** Once regions added, cannot add more or take away */
 pub struct Simulation<P,  T> where P: PopulationType, T: TransportAllocator<P>{
     pub geography: SimulationGeography<P>,
     allocator: T,
+    /** Allocators applied after allocator each update, in the order they were added */
+    extra_allocators: Vec<Box<dyn TransportAllocator<P>>>,
     pub ongoing_transport: Vec<InProgressJob>,
-    pub statistics: MediatorStatistics
+    pub statistics: MediatorStatistics,
+    /** People that have departed between each pair of regions */
+    pub flow_matrix: FlowMatrix,
+    /** ID to be given to the next created job */
+    next_job_id: u64,
+    /** Number of updates that have happened */
+    tick: u32,
+    /** Length of each update in ticks, for both travel and the pathogen */
+    dt: f64,
+    // time passed since populations last moved on a tick, they move once a whole tick has built up
+    unaged_time: f64,
+    closure_schedule: ClosureSchedule,
+    observers: Vec<Box<dyn SimulationObserver>>,
+    /** Population of each region when simulation was created, restored by reset */
+    initial_populations: HashMap<RegionID, P>,
+    /** Every port as it was when simulation was created, whose statuses are restored by reset */
+    initial_ports: Vec<Port>,
+    /** Applies pathogen to a region's population over the given number of ticks, if a pathogen was set */
+    pathogen_step: Option<Box<dyn Fn(Population, f64) -> Population>>,
+    /** Lets pathogen act on the whole geography after every region's pathogen step, along with the update's tick, see Pathogen::end_tick */
+    pathogen_end_tick: Option<PathogenEndTick<P>>,
+    /** Custom changes applied to every region each update, along with the update's tick */
+    region_rules: Vec<RegionRule<P>>,
+    update_order: UpdateOrder,
+    /** People infected by the pathogen during the current update, across all regions */
+    new_infections: u32,
+    /** Infected people at the start of the last update and people newly infected during it, if an update has happened */
+    last_infection_counts: Option<(u32, u32)>
 }
 
-impl<'a,P,T> Simulation< P, T> where P: PopulationType + 'a, T: TransportAllocator<P>{
+impl<'a,P,T> Simulation< P, T> where P: PopulationType + Clone + 'a, T: TransportAllocator<P>{
     pub fn new(geography: SimulationGeography<P>, allocator: T) -> Self {
         let total_pop = Self::calculate_regions_population(geography.get_regions());
-        Self {geography, ongoing_transport: vec![], statistics: MediatorStatistics::new(total_pop), allocator}
+        let initial_populations = geography.get_regions().map(|region| (region.id(), region.population.clone())).collect();
+        let initial_ports = geography.get_ports().into_iter().cloned().collect();
+        Self {geography, ongoing_transport: vec![], statistics: MediatorStatistics::new(total_pop), allocator, extra_allocators: vec![], flow_matrix: FlowMatrix::default(), next_job_id: 0, tick: 0, dt: 1.0, unaged_time: 0.0, closure_schedule: ClosureSchedule::default(), observers: vec![], initial_populations, initial_ports, pathogen_step: None, pathogen_end_tick: None, region_rules: vec![], update_order: UpdateOrder::default(), new_infections: 0, last_infection_counts: None}
+    }
+
+    /** Sets pathogen acting on every region's population once per update over dt ticks, people in transit are not affected */
+    /** After every region has been acted on, the pathogen's end_tick acts on the whole geography, e.g. to guarantee a spontaneous pathogen's first spawn */
+    #[allow(dead_code)]
+    pub fn set_pathogen<Q>(&mut self, pathogen: Q) where Q: Pathogen + 'static {
+        let pathogen = Rc::new(pathogen);
+        let step_pathogen = Rc::clone(&pathogen);
+        self.pathogen_step = Some(Box::new(move |population, dt| step_pathogen.calculate_population_over(population, dt)));
+        self.pathogen_end_tick = Some(Box::new(move |geography, tick| pathogen.end_tick(geography, tick)));
+    }
+
+    /** Adds allocator creating transports each update after the simulation's allocator and any allocators added before it */
+    /** Later allocators only move people not already departing through earlier allocators, so scheduled and random travel can be combined */
+    #[allow(dead_code)]
+    pub fn add_allocator(&mut self, allocator: Box<dyn TransportAllocator<P>>) {
+        self.extra_allocators.push(allocator);
+    }
+
+    /** Adds custom change applied to every region each update, such as a scripted quarantine, receiving the update's tick */
+    /** Rules are applied right after the pathogen, in the order they were added, and must not add ports to regions */
+    #[allow(dead_code)]
+    pub fn add_region_rule(&mut self, rule: RegionRule<P>) {
+        self.region_rules.push(rule);
+    }
+
+    /** Order in which the pathogen and travel are applied during each update, disease first by default */
+    #[allow(dead_code)]
+    pub fn update_order(&self) -> UpdateOrder {
+        self.update_order
+    }
+
+    #[allow(dead_code)]
+    pub fn set_update_order(&mut self, update_order: UpdateOrder) {
+        self.update_order = update_order;
+    }
+
+    /** Applies pathogen and then region rules to every region, in order of ID, then the pathogen's end of tick step, updating statistics by each change in population */
+    fn apply_region_updates(&mut self) {
+        if self.pathogen_step.is_none() && self.region_rules.is_empty() {
+            return;
+        }
+        let mut region_ids = self.geography.get_region_ids();
+        region_ids.sort();
+        for region_id in region_ids {
+            self.geography.with_region_mut(region_id, |region| {
+                if let Some(pathogen_step) = &self.pathogen_step {
+                    let old_population = region.population.population();
+                    let new_population = pathogen_step(old_population, self.dt);
+                    self.new_infections = self.new_infections.saturating_add(old_population.healthy.saturating_sub(new_population.healthy));
+                    region.population.set_population(new_population);
+                    self.statistics.record_region_change(old_population, region.population.population());
+                }
+                for rule in &self.region_rules {
+                    let old_population = region.population.population();
+                    rule(region, self.tick);
+                    self.statistics.record_region_change(old_population, region.population.population());
+                }
+            }).expect("Region IDs are taken from geography");
+        }
+        if let Some(pathogen_end_tick) = &self.pathogen_end_tick {
+            for (old_population, new_population) in pathogen_end_tick(&mut self.geography, self.tick).into_values() {
+                self.new_infections = self.new_infections.saturating_add(old_population.healthy.saturating_sub(new_population.healthy));
+                self.statistics.record_region_change(old_population, new_population);
+            }
+        }
+        self.statistics.update_peaks();
+        debug_assert!(self.statistics_consistent(), "Statistics do not match current state of simulation");
+    }
+
+    /** Returns simulation to the state it was created in, so the same scenario can be run again */
+    /** Restores region populations, along with any state they track such as immunity, and port statuses with their history */
+    /** Drops jobs in transit, zeroes the tick, statistics, flows and job IDs, and resets allocators */
+    /** Scheduled port changes, time step, pathogen, region rules and observers are kept */
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        for (region_id, population) in &self.initial_populations {
+            self.geography.with_region_mut(*region_id, |region| region.population = population.clone()).expect("Regions cannot be removed from simulation");
+        }
+        self.geography.restore_port_statuses(&self.initial_ports).expect("Ports cannot be removed from simulation");
+        self.allocator.reset();
+        for allocator in &mut self.extra_allocators {
+            allocator.reset();
+        }
+        self.ongoing_transport.clear();
+        self.flow_matrix = FlowMatrix::default();
+        self.next_job_id = 0;
+        self.tick = 0;
+        self.unaged_time = 0.0;
+        self.last_infection_counts = None;
+        self.statistics = MediatorStatistics::new(Self::calculate_regions_population(self.geography.get_regions()));
+    }
+
+    /** Rough effective reproduction number of the last update: people newly infected by the pathogen divided by people infected at its start */
+    /** None before the first update, or if nobody was infected at the start of the last update */
+    #[allow(dead_code)]
+    pub fn effective_r(&self) -> Option<f64> {
+        let (infected, new_infections) = self.last_infection_counts?;
+        if infected == 0 {
+            return None;
+        }
+        Some(f64::from(new_infections)/f64::from(infected))
+    }
+
+    /** Schedules port to change to given status at the start of the update on given tick */
+    /** Ticks count updates rather than elapsed time, so with a time step other than 1 the change happens after tick*dt time has passed */
+    /** Fails if port is not in simulation, or if tick has already passed */
+    #[allow(dead_code)]
+    pub fn schedule_port_status(&mut self, tick: u32, port_id: PortID, status: PortStatus) -> Result<(), String> {
+        if self.geography.get_port(port_id).is_none() {
+            return Err(format!("Cannot schedule status of port with ID {} because it wasn't found in simulation", port_id.0));
+        }
+        if tick < self.tick {
+            return Err(format!("Cannot schedule status of port with ID {} on tick {} because simulation is already on tick {}", port_id.0, tick, self.tick));
+        }
+        self.closure_schedule.schedule(tick, port_id, status);
+        Ok(())
+    }
+
+    /** Length of each update in ticks, 1.0 by default */
+    #[allow(dead_code)]
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    /** Sets length of each update in ticks, e.g. 0.5 makes jobs take twice as many updates to travel, the pathogen twice as many updates to spread, */
+    /** half as many people depart per update and immunity twice as many updates to run out */
+    /** Fails if dt is not positive and finite */
+    #[allow(dead_code)]
+    pub fn set_dt(&mut self, dt: f64) -> Result<(), String> {
+        if !(dt.is_finite() && dt > 0.0) {
+            return Err(format!("Time step must be positive and finite, not {dt}"));
+        }
+        self.dt = dt;
+        Ok(())
+    }
+
+    /** Adds observer to be notified of events from now on */
+    #[allow(dead_code)]
+    pub fn add_observer(&mut self, observer: Box<dyn SimulationObserver>) {
+        self.observers.push(observer);
+    }
+
+    /** Number of updates that have happened */
+    #[allow(dead_code)]
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /** Hashes tick, region populations and ongoing jobs, so that runs can be checked for determinism */
+    /** Identical runs produce identical hashes on every tick */
+    /** Region IDs are not hashed since they differ between runs, but regions are hashed in order of ID */
+    #[allow(dead_code)]
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.tick.hash(&mut hasher);
+        let mut region_ids = self.geography.get_region_ids();
+        region_ids.sort();
+        for region_id in region_ids {
+            Self::hash_population(&self.geography.get_region(region_id).unwrap().population.population(), &mut hasher);
+        }
+        for job in &self.ongoing_transport {
+            Self::hash_population(&job.job.population, &mut hasher);
+            job.job.time.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn hash_population(population: &Population, hasher: &mut impl Hasher) {
+        [population.healthy, population.infected, population.dead, population.recovered].hash(hasher);
     }
 
     /** Calculates population contained in simulation's regions */
     fn calculate_regions_population (regions: impl Iterator<Item = &'a Region<P>>) -> Population {
-        regions.map(|reg| reg.population.population()).fold(Population::new_healthy(0), |acc, pop| acc + pop.population())
+        regions.map(|reg| reg.population.population()).sum()
+    }
+
+    /** Population currently travelling between regions, counted from ongoing jobs rather than statistics */
+    /** Simulation owns jobs in transit, so geography only knows about people living in regions */
+    #[allow(dead_code)]
+    pub fn transit_population(&self) -> Population {
+        Self::calculate_transit_population(self.ongoing_transport.iter())
     }
 
-    /** Calculates population currently in transit */
+    /** Checks whether anyone in a region or in transit is still infected, such as for stopping run_until once an outbreak is over */
+    /** Unlike SimulationGeography::any_active_infection, infected travellers about to arrive in a region count */
+    #[allow(dead_code)]
+    pub fn any_active_infection(&self) -> bool {
+        self.geography.any_active_infection() || self.ongoing_transport.iter().any(|job| job.job.population.has_active_infection())
+    }
+
+    /** Calculates population currently in transit, saturating instead of overflowing when many large jobs are in flight */
     fn calculate_transit_population (jobs: impl Iterator<Item = &'a InProgressJob>) -> Population {
-        jobs.map(|job| job.job.population).fold(Population::new_healthy(0), |acc, pop| acc + pop)
+        jobs.fold(Population::default(), |total, job| total.saturating_add(job.job.population))
+    }
+
+    /** Calculates total people contained in simulation's regions and in transit, without risk of overflowing */
+    fn calculate_total_u64(&self) -> u64 {
+        let regions_total = self.geography.get_regions().fold(0_u64, |total, reg| total.saturating_add(reg.population.population().get_total_u64()));
+        let transit_total = self.ongoing_transport.iter().fold(0_u64, |total, job| total.saturating_add(job.job.population.get_total_u64()));
+        regions_total.saturating_add(transit_total)
     }
 
-    /** Updates statistics of simulation to reflect current state */
+    /** Checks whether statistics match statistics calculated from scratch */
+    fn statistics_consistent(&self) -> bool {
+        self.statistics.in_transit == Self::calculate_transit_population(self.ongoing_transport.iter())
+            && self.statistics.region_population == Self::calculate_regions_population(self.geography.get_regions())
+    }
+
+    /** Updates statistics of simulation to reflect current state by calculating them from scratch */
     fn update_statistics(&mut self) {
         self.statistics.in_transit = Self::calculate_transit_population(self.ongoing_transport.iter());
         self.statistics.region_population = Self::calculate_regions_population(self.geography.get_regions());
+        self.statistics.update_peaks();
+    }
+
+    /** Applies given changes to region, if found, then updates statistics to reflect them */
+    #[allow(dead_code)]
+    pub fn with_region_mut<F>(&mut self, region_id: RegionID, f: F) -> Result<(), String> where F: FnOnce(&mut Region<P>) {
+        self.geography.with_region_mut(region_id, f)?;
+        self.update_statistics();
+        Ok(())
+    }
+
+    /** Infects up to count healthy people of a random region, picked with probability proportional to its population */
+    /** Returns ID of region infected, or None if no region has any people */
+    #[allow(dead_code)]
+    pub fn seed_random_region(&mut self, count: u32) -> Option<RegionID> {
+        // regions are sorted so that picks are reproducible
+        let mut regions: Vec<(RegionID, u64)> = self.geography.get_regions()
+            .map(|region| (region.id(), region.population.population().get_total_u64()))
+            .collect();
+        regions.sort();
+        let weights: Vec<u64> = regions.iter().map(|(_, total)| *total).collect();
+        let (region_id, _) = *regions.get(pick_weighted(&weights)?)?;
+        self.geography.infect_region(region_id, count).ok()?;
+        self.update_statistics();
+        Some(region_id)
+    }
+
+    /** Progresses ongoing jobs, removing and returning jobs that finished */
+    fn finish_jobs(&mut self) -> Vec<TransportJob> {
+        let mut finished: Vec<TransportJob> = vec![];
+        for mut job in std::mem::take(&mut self.ongoing_transport) {
+            if job.remaining_time <= 0.0 {
+                finished.push(job.job);
+            } else {
+                job.remaining_time -= self.dt;
+                job.job.time = job.remaining_time.max(0.0).ceil() as u32;
+                self.ongoing_transport.push(job);
+            }
+        }
+        finished
+    }
+
+    /** Deposits people from finished jobs into their end regions */
+    /** Jobs finishing in the same region are deposited together, returns population deposited into each region */
+    fn deposit_arrivals(&mut self, finished: &[TransportJob]) -> HashMap<RegionID, Population> {
+        let mut arrivals: HashMap<RegionID, Population> = HashMap::new();
+        for job in finished {
+            let arrived = arrivals.entry(job.end_region).or_default();
+            *arrived = arrived.saturating_add(job.population);
+        }
+
+        // update end regions
+        for (end_region, population) in &arrivals {
+            if let Err(e) = self.geography.add_population(*end_region, *population) {
+                panic!("{}", format!("Region with ID {} that job is referring to doesn't exist in mediator. Error: {}", end_region, e));
+            }
+            self.statistics.record_arrival(*population);
+        }
+        arrivals
     }
 
     // create interactions between regions for each region
     // also updates populations of regions when people leave
     pub fn update(&mut self) {
+        let infected_at_start = self.statistics.region_population.infected.saturating_add(self.statistics.in_transit.infected);
+        self.new_infections = 0;
+
+        // apply scheduled port changes before any jobs are created
+        for (tick, port_id, status) in self.closure_schedule.events_at(self.tick) {
+            self.geography.set_port_status(*port_id, *status, *tick).expect("Scheduled port must be in simulation");
+        }
+
         // process jobs
-        self.ongoing_transport.retain_mut(|job| {
-            if job.job.time == 0 {
-                // update end region
-                let end_region = self.geography.get_region(job.job.end_region);
-                match end_region {
-                    Some(unwrapped_end_reg) => {
-                        self.geography.add_population(unwrapped_end_reg.id(), job.job.population);
-                        return  false;
-                    },
-                    None => panic!("{}", format!("Region with ID {} that job is referring to doesn't exist in mediator", job.job.end_region)),
-                }
-            } else {
-                job.job.time -= 1;
-                return true;
-            }
-        });
+        let finished = self.finish_jobs();
+        for job in &finished {
+            log::trace!("tick {}: job {:?} finished at port {} carrying {:?}", self.tick, job.job_id, job.end_port, job.population);
+        }
+        let arrivals = self.deposit_arrivals(&finished);
+        log::debug!("tick {}: {} jobs finished, {} regions received arrivals", self.tick, finished.len(), arrivals.len());
 
+        if self.update_order == UpdateOrder::DiseaseThenTravel {
+            self.apply_region_updates();
+        }
+
+        self.allocator.begin_tick(&self.geography);
+        for allocator in &mut self.extra_allocators {
+            allocator.begin_tick(&self.geography);
+        }
+        let allocators = Self::all_allocators(&self.allocator, &self.extra_allocators);
         let mut all_new_jobs: Vec<InProgressJob> = vec![];
 
         // generate new jobs
-        for region in self.geography.get_region_ids() {
-            let new_jobs = Self::calculate_transport_jobs(&mut self.geography, region, &self.allocator);
-            &all_new_jobs.extend(new_jobs);
+        // regions are visited in order of ID so that runs are reproducible
+        let mut region_ids = self.geography.get_region_ids();
+        region_ids.sort();
+        for region in region_ids {
+            let new_jobs = Self::calculate_transport_jobs(&self.geography, region, &allocators, self.dt, &mut self.next_job_id);
+            all_new_jobs.extend(new_jobs);
         }
 
         // for debugging purposes
-        let start_region_population = self.statistics.region_population.get_total();
-        let start_transit_population = self.statistics.in_transit.get_total();
+        let start_total_population = self.calculate_total_u64();
 
         // make people depart from regions after newly created jobs
         for job in &all_new_jobs {
             match self.geography.subtract_population(job.job.start_region, job.job.population) {
-                Ok(_) => (),
-                Err(e) => panic!("{}", format!("Failed to subtract {} people from region population of {} people. Error: {}", job.job.population.get_total(), self.geography.get_region(job.job.start_region).unwrap().population.population().get_total(), e))
+                Ok(_) => {
+                    self.statistics.record_departure(job.job.population);
+                    self.flow_matrix.record(job.job.start_region, job.job.end_region, job.job.population);
+                },
+                Err(e) => panic!("{}", format!("Failed to subtract {} people from region population of {} people. Error: {}", job.job.population.get_total_u64(), self.geography.get_region(job.job.start_region).unwrap().population.population().get_total_u64(), e))
             }
         }
 
+        for job in &all_new_jobs {
+            log::trace!("tick {}: job {} created from port {} to port {} carrying {:?}, taking {} ticks", self.tick, job.job_id(), job.job.start_port, job.job.end_port, job.job.population, job.expected_time);
+        }
+        log::debug!("tick {}: {} jobs created", self.tick, all_new_jobs.len());
         self.ongoing_transport.extend(all_new_jobs);
-
-        // update stats
-        self.update_statistics();
+        self.statistics.update_peaks();
 
         // for debugging purposes
-        let end_region_population = self.statistics.region_population.get_total();
-        let end_transit_population = self.statistics.in_transit.get_total();
+        let end_total_population = self.calculate_total_u64();
+        debug_assert!(self.statistics_consistent(), "Statistics do not match current state of simulation");
 
-        debug_assert_eq!(start_region_population + start_transit_population, 
-            end_region_population + end_transit_population,
-            "{}", format!("Previous region population: {} Previous transit population: {} New region population: {} New transit population: {}",
-            start_region_population, start_transit_population, end_region_population, end_transit_population));
+        debug_assert_eq!(start_total_population, end_total_population,
+            "{}", format!("Previous total population: {} New total population: {} Region population: {} Transit population: {}",
+            start_total_population, end_total_population, self.statistics.region_population.get_total_u64(), self.statistics.in_transit.get_total_u64()));
+
+        if self.update_order == UpdateOrder::TravelThenDisease {
+            self.apply_region_updates();
+        }
+
+        log::debug!("tick {}: {:?} living in regions, {:?} in transit", self.tick, self.statistics.region_population, self.statistics.in_transit);
+
+        // let populations track time, such as immunity running out, once for every whole tick that has passed
+        self.unaged_time += self.dt;
+        while self.unaged_time >= 1.0 {
+            self.unaged_time -= 1.0;
+            for (old_population, new_population) in self.geography.advance_populations().into_values() {
+                self.statistics.record_region_change(old_population, new_population);
+            }
+        }
+        self.statistics.update_peaks();
+        debug_assert!(self.statistics_consistent(), "Statistics do not match current state of simulation");
+
+        self.last_infection_counts = Some((infected_at_start, self.new_infections));
+
+        for observer in &mut self.observers {
+            observer.on_tick_end(self.tick, &finished);
+        }
+        self.tick += 1;
+    }
+
+    /** Updates simulation until stop returns true after a tick, or until max_ticks ticks have passed */
+    /** Returns number of ticks that were run */
+    #[allow(dead_code)]
+    pub fn run_until<F>(&mut self, max_ticks: u32, mut stop: F) -> u32 where F: FnMut(&Self) -> bool {
+        for tick in 1..=max_ticks {
+            self.update();
+            if stop(self) {
+                return tick;
+            }
+        }
+        max_ticks
+    }
+
+    /** Calculates transport jobs that the next update would create, without changing the simulation */
+    /** Random allocators are not guaranteed to create the same jobs during the next update */
+    #[allow(dead_code)]
+    pub fn preview_jobs(&self) -> Vec<TransportJob> {
+        let mut region_ids = self.geography.get_region_ids();
+        region_ids.sort();
+        let allocators = Self::all_allocators(&self.allocator, &self.extra_allocators);
+        region_ids.into_iter().flat_map(|region| Self::allocate_transport(&self.geography, region, &allocators, self.dt)).collect()
+    }
+
+    // every allocator in the order they are applied
+    fn all_allocators<'b>(allocator: &'b T, extra_allocators: &'b [Box<dyn TransportAllocator<P>>]) -> Vec<&'b dyn TransportAllocator<P>> {
+        let mut allocators: Vec<&dyn TransportAllocator<P>> = vec![allocator];
+        allocators.extend(extra_allocators.iter().map(|allocator| allocator.as_ref()));
+        allocators
     }
 
     // calculate transport jobs for a region
-    fn calculate_transport_jobs(geography: &SimulationGeography<P>, region_id: RegionID, allocator: &T) -> Vec<InProgressJob> {
+    // every created job is given a unique ID, starting from next_job_id
+    fn calculate_transport_jobs(geography: &SimulationGeography<P>, region_id: RegionID, allocators: &[&dyn TransportAllocator<P>], dt: f64, next_job_id: &mut u64) -> Vec<InProgressJob> {
         let mut new_jobs: Vec<InProgressJob> = vec![];
+        for job in Self::allocate_transport(geography, region_id, allocators, dt) {
+            new_jobs.push(InProgressJob::new(job, *next_job_id));
+            *next_job_id += 1;
+        }
+        new_jobs
+    }
+
+    // ask each allocator in turn for transports leaving each port of a region
+    // allocators give people departing per tick, so jobs are scaled by dt and those left empty are dropped
+    fn allocate_transport(geography: &SimulationGeography<P>, region_id: RegionID, allocators: &[&dyn TransportAllocator<P>], dt: f64) -> Vec<TransportJob> {
+        let mut new_jobs: Vec<TransportJob> = vec![];
         
         let region = geography.get_region(region_id).unwrap();
-        // look at each port
-        for port in region.get_ports() {
-            // where can each port go to?
-            let port_dests = geography.get_open_dest_ports(port.id).unwrap();
-
-            // calculate transport jobs
-            let calculated_jobs = allocator.calculate_transport(port, region, port_dests);
-            for job in calculated_jobs.unwrap_or(vec![]) {
-                match region.population.population().emigrate(job.population) {
-                    Ok(new_pop) => {
-                        // assume transportation takes 2 days
-                        new_jobs.push(InProgressJob::new(job))
-                    },
-                    Err(e) => panic!("{}", e),
+        // people not yet taken by an earlier job, shared by all allocators so departures never exceed region population
+        let mut remaining_population = region.population.population();
+        // look at each port, in order of ID so that runs are reproducible
+        let mut ports: Vec<&Port> = region.get_ports().iter().collect();
+        ports.sort_by_key(|port| port.id);
+        for allocator in allocators {
+            // people this allocator's jobs so far would leave behind
+            let mut allocator_remaining = remaining_population;
+            let mut allocator_jobs: Vec<TransportJob> = vec![];
+            for port in &ports {
+                // where can each port go to?
+                let port_dests = geography.get_open_dest_ports(port.id).unwrap();
+
+                // calculate transport jobs
+                // allocator sees the entire region, so skip jobs needing people already taken by other ports or allocators
+                let calculated_jobs = allocator.calculate_transport(port, region, port_dests);
+                for job in calculated_jobs.unwrap_or(vec![]) {
+                    if let Ok(new_pop) = allocator_remaining.emigrate(job.population) {
+                        allocator_remaining = new_pop;
+                        allocator_jobs.push(job);
+                    }
+                }
+            }
+            // allocator adjusts the region's jobs together, such as capping departures, before they are scaled to the time step
+            allocator.end_region(region, &mut allocator_jobs);
+            for mut job in allocator_jobs {
+                if dt != 1.0 {
+                    // jobs too large to scale could never depart from a region anyway
+                    match job.population.scale_probabilistic(dt) {
+                        Ok(population) if population.get_total_u64() > 0 => job.population = population,
+                        _ => continue
+                    }
+                }
+                if let Ok(new_pop) = remaining_population.emigrate(job.population) {
+                    remaining_population = new_pop;
+                    new_jobs.push(job);
                 }
             }
         }
@@ -134,12 +627,21 @@ impl<'a,P,T> Simulation< P, T> where P: PopulationType + 'a, T: TransportAllocat
 
 pub struct InProgressJob {
     pub job: TransportJob,
-    pub expected_time: u32
+    pub expected_time: u32,
+    /** Time left until job finishes, which may be fractional when ticks are, job's time is this rounded up */
+    pub remaining_time: f64
 }
 
 impl InProgressJob {
-    pub fn new(job: TransportJob) -> Self {
-        Self {expected_time: job.time, job}
+    /** Tags job with given ID and begins tracking its progress */
+    pub fn new(mut job: TransportJob, job_id: u64) -> Self {
+        job.job_id = Some(job_id);
+        Self {expected_time: job.time, remaining_time: job.time as f64, job}
+    }
+
+    /** ID given to the job when it began, stored on the job itself */
+    pub fn job_id(&self) -> u64 {
+        self.job.job_id.expect("In progress jobs are always tagged with an ID")
     }
 }
 
@@ -147,10 +649,13 @@ impl InProgressJob {
 mod tests {
 
 
-    use functionality::{config::{load_config_data, ConfigData}, point::Point2D, population_types::population::Population, region::{PortID, Region}, simulation_geography::SimulationGeography, transportation_allocator::RandomTransportAllocator, transportation_graph::PortGraph};
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use functionality::{config::{load_config_data, ConfigData}, math_utils::seed_random, pathogen::pathogen_types::{pathogen::{Pathogen, PathogenStruct}, spontaneous_pathogen::SpontaneousPathogen}, point::Point2D, population_types::{populated_area::PopulatedArea, population::Population, temporary_immunity_population::TemporaryImmunityPopulation, PopulationType}, region::{Port, PortID, PortStatus, Region, RegionID}, simulation_geography::SimulationGeography, transportation_allocator::{DepartureCapAllocator, RandomTransportAllocator, TransportAllocator, TransportJob}, transportation_graph::PortGraph};
 
 
-    use super::Simulation;
+    use super::{FlowMatrix, InProgressJob, Simulation, SimulationObserver, UpdateOrder};
+    use crate::test_support::small_world;
 
 
     #[test]
@@ -158,10 +663,10 @@ mod tests {
     fn test_intra_country_transport() {
         let china_pop = 5000;
         let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
-        let port1 = china.add_port(PortID(1), 100, Point2D::default());
-        let port2 = china.add_port(PortID(2), 200, Point2D::default());
-        let port3 = china.add_port(PortID(3), 500, Point2D::default());
-        let port4 = china.add_port(PortID(4), 50, Point2D::default());
+        let port1 = china.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        let port2 = china.add_port(PortID(2), 200, Point2D::default()).unwrap();
+        let port3 = china.add_port(PortID(3), 500, Point2D::default()).unwrap();
+        let port4 = china.add_port(PortID(4), 50, Point2D::default()).unwrap();
 
         let mut graph = PortGraph::new();
         graph.add_port(port1);
@@ -176,7 +681,7 @@ mod tests {
         graph.add_directed_connection(PortID(3), PortID(1));
 
         // make simulation
-        let mut sim: Simulation<Point2D, Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china]), RandomTransportAllocator::new(1.0));
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china]), RandomTransportAllocator::new(1.0));
 
         // make sure that number of people living in regions plus number in transit always stays same
         let total = sim.statistics.in_transit + sim.statistics.region_population;
@@ -192,7 +697,7 @@ mod tests {
         let config = load_config_data("test_data/data.json").unwrap();
      
         // make simulation
-        let mut sim: Simulation<Point2D, Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(config.graph, config.regions), RandomTransportAllocator::new(1.0));
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(config.graph, config.regions), RandomTransportAllocator::new(1.0));
 
         // make sure that number of people living in regions plus number in transit always stays same
         let total = sim.statistics.in_transit + sim.statistics.region_population;
@@ -217,7 +722,7 @@ mod tests {
 
         // create mediator, add regions
         // make simulation
-        let mut sim: Simulation<Point2D, Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, config.regions), RandomTransportAllocator::new(1.0));
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, config.regions), RandomTransportAllocator::new(1.0));
 
         // make sure that number of people living in regions plus number in transit always stays same
         let total = sim.statistics.in_transit + sim.statistics.region_population;
@@ -226,5 +731,947 @@ mod tests {
             assert_eq!(sim.statistics.in_transit + sim.statistics.region_population, total);
         }
     }
-}
 
+    #[test]
+    /** Tests that every job created during a simulation has a unique, increasing ID */
+    fn test_job_ids_increasing() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let port1 = china.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        let port2 = china.add_port(PortID(2), 200, Point2D::new(10.0, 10.0)).unwrap();
+
+        let mut graph = PortGraph::new();
+        graph.add_port(port1);
+        graph.add_port(port2);
+        graph.add_undirected_connection(PortID(1), PortID(2));
+
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china]), RandomTransportAllocator::new(1.0));
+
+        let mut seen_ids: Vec<u64> = vec![];
+        for _ in 0..=20 {
+            sim.update();
+            for job in &sim.ongoing_transport {
+                if !seen_ids.contains(&job.job_id()) {
+                    seen_ids.push(job.job_id());
+                }
+            }
+        }
+        assert!(!seen_ids.is_empty());
+        // jobs are seen in order of creation, so IDs must be strictly increasing
+        assert!(seen_ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    /** Tests that simulations seeded identically create identical jobs */
+    fn test_seeded_runs_identical() {
+        let run = |seed: u64| {
+            seed_random(seed);
+            let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+            let us_port1 = us.add_port(PortID(3), 100, Point2D::new(0.0, 0.0)).unwrap();
+            let us_port2 = us.add_port(PortID(1), 200, Point2D::new(5.0, 0.0)).unwrap();
+            let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+            let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 8.0)).unwrap();
+
+            let mut graph = PortGraph::new();
+            graph.add_port(us_port1);
+            graph.add_port(us_port2);
+            graph.add_port(mexico_port);
+            graph.add_undirected_connection(PortID(1), PortID(2));
+            graph.add_undirected_connection(PortID(1), PortID(3));
+            graph.add_undirected_connection(PortID(2), PortID(3));
+
+            let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![mexico, us]), RandomTransportAllocator::new(0.5));
+            let mut jobs: Vec<String> = vec![];
+            for _ in 0..=20 {
+                sim.update();
+                for job in &sim.ongoing_transport {
+                    jobs.push(format!("{:?} {:?} {:?} {:?} {}", job.job.job_id, job.job.start_port, job.job.end_port, job.job.population, job.job.time));
+                }
+            }
+            jobs
+        };
+
+        let first_run = run(7);
+        assert!(!first_run.is_empty());
+        assert_eq!(first_run, run(7));
+    }
+
+    #[test]
+    /** Tests that directly changing a region is reflected in statistics */
+    fn test_with_region_mut() {
+        let china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let china_id = china.id();
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![china]), RandomTransportAllocator::new(1.0));
+        assert_eq!(sim.statistics.region_population, Population::new_healthy(5000));
+
+        sim.with_region_mut(china_id, |region| region.population = Population {healthy: 4000, infected: 1000, dead: 0, recovered: 0}).unwrap();
+        assert_eq!(sim.statistics.region_population, Population {healthy: 4000, infected: 1000, dead: 0, recovered: 0});
+
+        assert!(sim.with_region_mut(RegionID(9999), |region| region.population = Population::default()).is_err());
+    }
+
+    #[test]
+    fn test_many_large_jobs_in_transit() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let port = china.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut graph = PortGraph::new();
+        graph.add_port(port).unwrap();
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china]), RandomTransportAllocator::new(0.0));
+
+        let large = Population {healthy: u32::MAX / 2, infected: u32::MAX / 2, dead: u32::MAX / 2, recovered: u32::MAX / 2};
+        for job_id in 0..1000 {
+            let job = TransportJob {job_id: None, start_port: PortID(0), start_region: china_id, end_port: PortID(0), end_region: china_id, population: large, time: 1000};
+            sim.ongoing_transport.push(InProgressJob::new(job, job_id));
+        }
+        sim.with_region_mut(china_id, |_| {}).unwrap();
+        assert_eq!(sim.statistics.in_transit, Population {healthy: u32::MAX, infected: u32::MAX, dead: u32::MAX, recovered: u32::MAX});
+
+        for _ in 0..5 {
+            sim.update();
+        }
+        assert_eq!(sim.ongoing_transport.len(), 1000);
+        assert_eq!(sim.geography.get_population(china_id).unwrap(), &Population::new_healthy(5000));
+    }
+
+    #[test]
+    fn test_any_active_infection() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let port = china.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut graph = PortGraph::new();
+        graph.add_port(port).unwrap();
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china]), RandomTransportAllocator::new(0.0));
+        assert!(!sim.any_active_infection());
+
+        // infected travellers in flight keep the outbreak going, even though no region has infected people
+        let job = TransportJob {job_id: None, start_port: PortID(0), start_region: china_id, end_port: PortID(0), end_region: china_id, population: Population {healthy: 0, infected: 10, dead: 0, recovered: 0}, time: 3};
+        sim.ongoing_transport.push(InProgressJob::new(job, 0));
+        sim.with_region_mut(china_id, |_| {}).unwrap();
+        assert!(!sim.geography.any_active_infection());
+        assert!(sim.any_active_infection());
+        assert_eq!(sim.run_until(2, |sim| !sim.any_active_infection()), 2);
+
+        // once they arrive, the region is infected
+        while !sim.ongoing_transport.is_empty() {
+            sim.update();
+        }
+        assert!(sim.geography.any_active_infection());
+        assert!(sim.any_active_infection());
+    }
+
+    #[test]
+    fn test_statistics_peaks() {
+        let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        let us_port = us.add_port(PortID(1), 100, Point2D::new(0.0, 0.0)).unwrap();
+        let us_id = us.id();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 5.0)).unwrap();
+        let graph = PortGraph::from_edges(vec![us_port, mexico_port], vec![(PortID(1), PortID(2))]).unwrap();
+
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(1.0));
+        assert_eq!((sim.statistics.peak_infected, sim.statistics.peak_in_transit), (0, 0));
+
+        // stop new departures part way through so that everyone eventually arrives
+        sim.schedule_port_status(5, PortID(2), PortStatus::Closed).unwrap();
+        let mut max_in_transit = 0;
+        for _ in 0..20 {
+            sim.update();
+            max_in_transit = max_in_transit.max(sim.statistics.in_transit.get_total());
+            assert_eq!(sim.statistics.peak_in_transit, max_in_transit);
+        }
+        assert!(sim.statistics.peak_in_transit > 0);
+        assert_eq!(sim.statistics.in_transit.get_total(), 0);
+
+        sim.with_region_mut(us_id, |region| region.population.infected = 500).unwrap();
+        sim.with_region_mut(us_id, |region| region.population.infected = 200).unwrap();
+        assert_eq!(sim.statistics.peak_infected, 500);
+        assert_eq!(sim.statistics.region_population.infected, 200);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        let us_port1 = us.add_port(PortID(3), 100, Point2D::new(0.0, 0.0)).unwrap();
+        let us_port2 = us.add_port(PortID(1), 200, Point2D::new(5.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 8.0)).unwrap();
+        let graph = PortGraph::from_edges(vec![us_port1, us_port2, mexico_port], vec![(PortID(1), PortID(2)), (PortID(2), PortID(3)), (PortID(3), PortID(1))]).unwrap();
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![mexico, us]), RandomTransportAllocator::new(0.5));
+        sim.schedule_port_status(5, PortID(2), PortStatus::Closed).unwrap();
+        let initial_hash = sim.state_hash();
+
+        let mut run = |sim: &mut Simulation<Population, RandomTransportAllocator>| {
+            seed_random(5);
+            (0..20).map(|_| {
+                sim.update();
+                (sim.state_hash(), sim.ongoing_transport.iter().map(|job| job.job_id()).collect::<Vec<u64>>())
+            }).collect::<Vec<_>>()
+        };
+        let first_run = run(&mut sim);
+        assert!(!sim.ongoing_transport.is_empty());
+        assert_eq!(sim.geography.get_port(PortID(2)).unwrap().port_status(), PortStatus::Closed);
+
+        sim.reset();
+        assert_eq!(sim.tick(), 0);
+        assert!(sim.ongoing_transport.is_empty());
+        assert_eq!(sim.statistics.region_population, Population::new_healthy(8000));
+        assert_eq!(sim.statistics.peak_in_transit, 0);
+        for port in sim.geography.get_ports() {
+            assert_eq!(port.port_status(), PortStatus::Open);
+            assert!(port.status_history().is_empty());
+        }
+        assert_eq!(sim.state_hash(), initial_hash);
+        assert_eq!(run(&mut sim), first_run);
+        assert_eq!(sim.geography.get_port(PortID(2)).unwrap().status_history(), vec![(5, PortStatus::Closed)]);
+
+        // immunity starts over as well, rather than carrying on from before the reset
+        let population = TemporaryImmunityPopulation::new(Population {healthy: 50, infected: 0, dead: 0, recovered: 30}, 3).unwrap();
+        let geography = SimulationGeography::new(PortGraph::new(), vec![Region::new("Atlantis".to_owned(), population)]);
+        let mut sim = Simulation::new(geography, RandomTransportAllocator::new(0.0));
+        sim.update();
+        sim.update();
+        sim.reset();
+        assert_eq!(sim.run_until(10, |sim| sim.statistics.region_population.recovered == 0), 3);
+    }
+
+    // doubles the number of infected each tick
+    struct DoublingPathogen;
+
+    impl Pathogen for DoublingPathogen {
+        fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+            let prev = population.population();
+            let newly_infected = prev.infected.min(prev.healthy);
+            let mut output = population;
+            output.set_population(Population {healthy: prev.healthy - newly_infected, infected: prev.infected + newly_infected, ..prev});
+            output
+        }
+    }
+
+    struct TriplingPathogen;
+
+    impl Pathogen for TriplingPathogen {
+        fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+            let prev = population.population();
+            let newly_infected = (prev.infected * 2).min(prev.healthy);
+            let mut output = population;
+            output.set_population(Population {healthy: prev.healthy - newly_infected, infected: prev.infected + newly_infected, ..prev});
+            output
+        }
+    }
+
+    #[test]
+    fn test_departure_cap_covers_whole_region() {
+        seed_random(8);
+        let (geography, _) = small_world();
+        let mut sim = Simulation::new(geography, DepartureCapAllocator::new(RandomTransportAllocator::new(1.0), 0.01).unwrap());
+        for _ in 0..20 {
+            let mut departures: HashMap<RegionID, u32> = HashMap::new();
+            for job in sim.preview_jobs() {
+                *departures.entry(job.start_region).or_default() += job.population.get_total();
+            }
+            for (region_id, departed) in departures {
+                let alive = sim.geography.get_population(region_id).unwrap().get_alive();
+                assert!(f64::from(departed) <= 0.01*f64::from(alive), "{departed} of {alive} people departed");
+            }
+            sim.update();
+        }
+    }
+
+    #[test]
+    fn test_spontaneous_pathogen_first_spawn() {
+        seed_random(3);
+        let regions = vec![
+            Region::new("United States".to_owned(), Population::new_healthy(5000)),
+            Region::new("Mexico".to_owned(), Population::new_healthy(3000)),
+        ];
+        let mut sim = Simulation::new(SimulationGeography::new(PortGraph::new(), regions), RandomTransportAllocator::new(0.0));
+        sim.set_pathogen(SpontaneousPathogen::with_guaranteed_first_spawn(0.0, DoublingPathogen, 3));
+
+        for _ in 0..3 {
+            sim.update();
+            assert_eq!(sim.statistics.region_population.infected, 0);
+        }
+        // spawned at the end of the update on tick 3, and spreads from then on
+        sim.update();
+        assert_eq!(sim.statistics.region_population.infected, 1);
+        assert_eq!(sim.geography.get_regions().filter(|region| region.population.infected > 0).count(), 1);
+        sim.update();
+        assert_eq!(sim.statistics.region_population.infected, 2);
+        assert_eq!(sim.effective_r(), Some(1.0));
+    }
+
+    #[test]
+    fn test_effective_r() {
+        let (geography, allocator) = small_world();
+        let mut sim = Simulation::new(geography, allocator);
+        assert_eq!(sim.effective_r(), None);
+        sim.set_pathogen(PathogenStruct::new("Harmless".to_owned(), 0.0, 0.0).unwrap());
+        for _ in 0..5 {
+            sim.update();
+            assert_eq!(sim.effective_r(), Some(0.0));
+        }
+
+        sim.reset();
+        assert_eq!(sim.effective_r(), None);
+        sim.set_pathogen(TriplingPathogen);
+        sim.update();
+        // every infected person infects two more, except for those in transit
+        let r = sim.effective_r().unwrap();
+        assert!(r > 1.0 && r <= 2.0, "{r}");
+
+        // nobody infected, so no R can be given
+        let (geography, allocator) = small_world();
+        let mut healthy = Simulation::new(geography, allocator);
+        let mexico_id = healthy.geography.get_region_by_name("Mexico").unwrap().id();
+        healthy.with_region_mut(mexico_id, |region| region.population = Population::new_healthy(3000)).unwrap();
+        healthy.set_pathogen(TriplingPathogen);
+        healthy.update();
+        assert_eq!(healthy.effective_r(), None);
+    }
+
+    #[test]
+    fn test_update_order() {
+        let run = |update_order: UpdateOrder| {
+            seed_random(3);
+            let mut china = Region::new("China".to_owned(), Population {healthy: 9000, infected: 1000, dead: 0, recovered: 0});
+            let china_port = china.add_port(PortID(0), 5000, Point2D::new(0.0, 0.0)).unwrap();
+            let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(10000));
+            let benin_port = benin.add_port(PortID(1), 0, Point2D::new(0.0, 10.0)).unwrap();
+            let graph = PortGraph::from_edges(vec![china_port, benin_port], vec![(PortID(0), PortID(1))]).unwrap();
+
+            let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![china, benin]), RandomTransportAllocator::new(1.0));
+            assert_eq!(sim.update_order(), UpdateOrder::DiseaseThenTravel);
+            sim.set_update_order(update_order);
+            sim.set_pathogen(DoublingPathogen);
+            sim.update();
+            assert!(!sim.ongoing_transport.is_empty());
+            sim.statistics.in_transit.infected + sim.statistics.region_population.infected
+        };
+
+        // every infected person is doubled before anyone leaves
+        assert_eq!(run(UpdateOrder::DiseaseThenTravel), 2000);
+        // infected people that left are not doubled
+        assert!(run(UpdateOrder::TravelThenDisease) < 2000);
+    }
+
+    #[test]
+    fn test_small_world() {
+        let run = || {
+            let (geography, allocator) = small_world();
+            let mut sim = Simulation::new(geography, allocator);
+            let total = sim.statistics.in_transit.get_total_u64() + sim.statistics.region_population.get_total_u64();
+            assert_eq!(total, 16000);
+            let mut hashes = vec![];
+            for _ in 0..20 {
+                sim.update();
+                assert_eq!(sim.statistics.in_transit.get_total_u64() + sim.statistics.region_population.get_total_u64(), total);
+                hashes.push(sim.state_hash());
+            }
+            assert!(!sim.ongoing_transport.is_empty());
+            hashes
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_transit_population() {
+        let (geography, allocator) = small_world();
+        let mut sim = Simulation::new(geography, allocator);
+        assert_eq!(sim.transit_population(), Population::default());
+        for _ in 0..5 {
+            sim.update();
+        }
+        let transit = sim.transit_population();
+        assert!(transit.get_total() > 0);
+        assert_eq!(transit, sim.statistics.in_transit);
+        let regions_total: u64 = sim.geography.get_regions().map(|region| region.population.get_total_u64()).sum();
+        assert_eq!(regions_total + transit.get_total_u64(), 16000);
+    }
+
+    thread_local! {
+        // log records emitted by the current test's thread
+        static CAPTURED_LOGS: RefCell<Vec<(log::Level, String)>> = const { RefCell::new(vec![]) };
+    }
+
+    // stores records per thread, so that tests running in parallel do not see each other's logs
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.with(|logs| logs.borrow_mut().push((record.level(), record.args().to_string())));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger;
+
+    #[test]
+    fn test_update_logging() {
+        // logger may already have been set by another test
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let (geography, allocator) = small_world();
+        let mut sim = Simulation::new(geography, allocator);
+        for _ in 0..10 {
+            sim.update();
+        }
+        let logs = CAPTURED_LOGS.with(|logs| logs.take());
+        let has_log = |level: log::Level, text: &str| logs.iter().any(|(log_level, message)| *log_level == level && message.contains(text));
+        assert!(has_log(log::Level::Debug, "tick 0: 0 jobs finished"));
+        assert!(has_log(log::Level::Debug, "jobs created"));
+        assert!(has_log(log::Level::Debug, "tick 9: Population"));
+        assert!(has_log(log::Level::Trace, "created from port"));
+        assert!(has_log(log::Level::Trace, "finished at port"));
+    }
+
+    #[test]
+    fn test_region_rule() {
+        let china = Region::new("China".to_owned(), Population {healthy: 1000, infected: 1600, dead: 0, recovered: 0});
+        let benin = Region::new("Benin".to_owned(), Population {healthy: 1000, infected: 0, dead: 0, recovered: 0});
+        let (china_id, benin_id) = (china.id(), benin.id());
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![china, benin]), RandomTransportAllocator::new(0.0));
+
+        // quarantine moves half of the infected to recovered each tick
+        sim.add_region_rule(Box::new(|region, _tick| {
+            let quarantined = region.population.infected / 2;
+            region.population.infected -= quarantined;
+            region.population.recovered += quarantined;
+        }));
+        let ticks_seen = Rc::new(RefCell::new(vec![]));
+        let rule_ticks = Rc::clone(&ticks_seen);
+        sim.add_region_rule(Box::new(move |region, tick| {
+            if region.name == "China" {
+                rule_ticks.borrow_mut().push(tick);
+            }
+        }));
+
+        for expected_infected in [800, 400, 200, 100] {
+            sim.update();
+            assert_eq!(sim.geography.get_population(china_id).unwrap().infected, expected_infected);
+        }
+        assert_eq!(*sim.geography.get_population(china_id).unwrap(), Population {healthy: 1000, infected: 100, dead: 0, recovered: 1500});
+        assert_eq!(*sim.geography.get_population(benin_id).unwrap(), Population::new_healthy(1000));
+        assert_eq!(sim.statistics.region_population.infected, 100);
+        assert_eq!(*ticks_seen.borrow(), vec![0, 1, 2, 3]);
+    }
+
+    // records calls it receives, without creating any jobs
+    struct RecordingAllocator {
+        calls: Rc<RefCell<Vec<&'static str>>>
+    }
+
+    impl TransportAllocator for RecordingAllocator {
+        fn calculate_transport<'a>(&self, _start_port: &Port, _start_region: &Region, _destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
+            self.calls.borrow_mut().push("port");
+            None
+        }
+
+        fn begin_tick(&mut self, _geography: &SimulationGeography<Population>) {
+            self.calls.borrow_mut().push("begin");
+        }
+    }
+
+    /** Sends a fixed number of healthy people from every port to its lowest ID destination each update */
+    struct ScheduledFlightAllocator {
+        passengers: u32
+    }
+
+    impl TransportAllocator for ScheduledFlightAllocator {
+        fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
+            let end_port = destination_port_choices.into_iter().min_by_key(|port| port.id)?;
+            if start_region.population.healthy < self.passengers {
+                return None;
+            }
+            Some(vec![TransportJob {job_id: None, start_port: start_port.id, start_region: start_region.id(), end_port: end_port.id, end_region: end_port.region(), population: Population::new_healthy(self.passengers), time: 2}])
+        }
+    }
+
+    #[test]
+    fn test_temporary_immunity_expires() {
+        let population = TemporaryImmunityPopulation::new(Population {healthy: 50, infected: 0, dead: 0, recovered: 30}, 2).unwrap();
+        let geography = SimulationGeography::new(PortGraph::new(), vec![Region::new("Atlantis".to_owned(), population)]);
+        let mut sim = Simulation::new(geography, RandomTransportAllocator::new(0.0));
+        sim.update();
+        assert_eq!(sim.statistics.region_population, Population {healthy: 50, infected: 0, dead: 0, recovered: 30});
+        sim.update();
+        assert_eq!(sim.statistics.region_population, Population {healthy: 80, infected: 0, dead: 0, recovered: 0});
+    }
+
+    #[test]
+    fn test_multiple_allocators() {
+        let (geography, allocator) = small_world();
+        let mut sim = Simulation::new(geography, ScheduledFlightAllocator {passengers: 40});
+        sim.add_allocator(Box::new(allocator));
+        sim.update();
+        let flights = sim.ongoing_transport.iter().filter(|job| job.job.population == Population::new_healthy(40) && job.job.time == 2).count();
+        assert!(flights >= 4, "{flights}");
+
+        for _ in 0..50 {
+            sim.update();
+            assert_eq!(sim.calculate_total_u64(), 16000);
+            assert_eq!(sim.statistics.region_population.get_total_u64() + sim.statistics.in_transit.get_total_u64(), 16000);
+        }
+
+        // allocators after the first only move people that are left
+        let (geography, _) = small_world();
+        let mut sim = Simulation::new(geography, ScheduledFlightAllocator {passengers: 2500});
+        sim.add_allocator(Box::new(ScheduledFlightAllocator {passengers: 2500}));
+        sim.update();
+        assert_eq!(sim.calculate_total_u64(), 16000);
+        // both allocators want 2500 of Mexico's 3000 people, so only the first one's flight leaves
+        let mexico = sim.geography.get_region_by_name("Mexico").unwrap();
+        assert_eq!(sim.ongoing_transport.iter().filter(|job| job.job.start_region == mexico.id()).count(), 1);
+        assert_eq!(mexico.population.get_total(), 500);
+    }
+
+    #[test]
+    fn test_allocator_begin_tick() {
+        let (geography, _) = small_world();
+        let port_count = geography.port_count();
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut sim = Simulation::new(geography, RecordingAllocator {calls: Rc::clone(&calls)});
+        for _ in 0..3 {
+            sim.update();
+        }
+        // begin_tick fires once per tick, before every port is asked for jobs
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 3 * (port_count + 1));
+        for tick_calls in calls.chunks(port_count + 1) {
+            assert_eq!(tick_calls[0], "begin");
+            assert!(tick_calls[1..].iter().all(|call| *call == "port"));
+        }
+    }
+
+    #[test]
+    fn test_seed_random_region() {
+        let small = Region::new("Benin".to_owned(), Population::new_healthy(1000));
+        let large = Region::new("China".to_owned(), Population::new_healthy(3000));
+        let empty = Region::new("Atlantis".to_owned(), Population::default());
+        let (small_id, large_id) = (small.id(), large.id());
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![small, large, empty]), RandomTransportAllocator::new(0.0));
+
+        let mut picks: HashMap<RegionID, u32> = HashMap::new();
+        for seed in 0..2000 {
+            seed_random(seed);
+            let region_id = sim.seed_random_region(0).unwrap();
+            *picks.entry(region_id).or_default() += 1;
+        }
+        assert_eq!(picks.len(), 2);
+        assert!((350..650).contains(&picks[&small_id]));
+        assert!((1350..1650).contains(&picks[&large_id]));
+
+        let region_id = sim.seed_random_region(10).unwrap();
+        assert_eq!(sim.geography.get_population(region_id).unwrap().infected, 10);
+        assert_eq!(sim.statistics.region_population.infected, 10);
+
+        let mut empty_sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![Region::new("Atlantis".to_owned(), Population::default())]), RandomTransportAllocator::new(0.0));
+        assert!(empty_sim.seed_random_region(1).is_none());
+    }
+
+    #[test]
+    /** Tests that previewing jobs does not change the simulation */
+    fn test_preview_jobs() {
+        let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        let us_port = us.add_port(PortID(1), 100, Point2D::new(0.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 8.0)).unwrap();
+
+        let mut graph = PortGraph::new();
+        graph.add_port(us_port);
+        graph.add_port(mexico_port);
+        graph.add_undirected_connection(PortID(1), PortID(2));
+
+        let sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(1.0));
+        let populations = sim.geography.population_snapshot();
+        for _ in 0..=20 {
+            let jobs = sim.preview_jobs();
+            for job in &jobs {
+                assert!(job.job_id.is_none());
+                assert!(populations[&job.start_region].emigrate(job.population).is_ok());
+            }
+            assert_eq!(sim.geography.population_snapshot(), populations);
+            assert!(sim.ongoing_transport.is_empty());
+        }
+    }
+
+    #[test]
+    /** Tests that statistics updated each tick match statistics calculated from scratch */
+    fn test_incremental_statistics() {
+        let mut us = Region::new("United States".to_owned(), Population::new_random(5000));
+        let us_port1 = us.add_port(PortID(1), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let us_port2 = us.add_port(PortID(2), 200, Point2D::new(3.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_random(3000));
+        let mexico_port = mexico.add_port(PortID(3), 300, Point2D::new(0.0, 2.0)).unwrap();
+
+        let mut graph = PortGraph::new();
+        graph.add_port(us_port1);
+        graph.add_port(us_port2);
+        graph.add_port(mexico_port);
+        graph.add_undirected_connection(PortID(1), PortID(2));
+        graph.add_undirected_connection(PortID(1), PortID(3));
+        graph.add_undirected_connection(PortID(2), PortID(3));
+
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(0.7));
+        for _ in 0..50 {
+            sim.update();
+            let in_transit = Simulation::<Population, RandomTransportAllocator>::calculate_transit_population(sim.ongoing_transport.iter());
+            let region_population = Simulation::<Population, RandomTransportAllocator>::calculate_regions_population(sim.geography.get_regions());
+            assert_eq!(sim.statistics.in_transit, in_transit);
+            assert_eq!(sim.statistics.region_population, region_population);
+        }
+    }
+
+    #[test]
+    /** Tests that running stops once the condition is met, or after running out of ticks */
+    fn test_run_until() {
+        let make_sim = || {
+            let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+            let us_port = us.add_port(PortID(1), 100, Point2D::new(0.0, 0.0)).unwrap();
+            let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+            let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 8.0)).unwrap();
+
+            let mut graph = PortGraph::new();
+            graph.add_port(us_port);
+            graph.add_port(mexico_port);
+            graph.add_undirected_connection(PortID(1), PortID(2));
+            let sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(1.0));
+            sim
+        };
+
+        // stops early once condition is met
+        let mut sim = make_sim();
+        let mut checks = 0;
+        let ticks = sim.run_until(100, |_| {
+            checks += 1;
+            checks == 3
+        });
+        assert_eq!(ticks, 3);
+        assert_eq!(checks, 3);
+
+        // otherwise runs all ticks
+        let mut sim = make_sim();
+        let mut checks = 0;
+        let ticks = sim.run_until(25, |_| {
+            checks += 1;
+            false
+        });
+        assert_eq!(ticks, 25);
+        assert_eq!(checks, 25);
+        assert_eq!(sim.run_until(0, |_| true), 0);
+    }
+
+    #[test]
+    /** Tests simulations whose regions track population density */
+    fn test_populated_area_simulation() {
+        let mut us = Region::new("United States".to_owned(), PopulatedArea::new_from_area(100.0, Population::new_random(5000)).unwrap());
+        let us_port1 = us.add_port(PortID(1), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let us_port2 = us.add_port(PortID(2), 200, Point2D::new(3.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), PopulatedArea::new_from_area(40.0, Population::new_random(3000)).unwrap());
+        let mexico_port = mexico.add_port(PortID(3), 300, Point2D::new(0.0, 2.0)).unwrap();
+
+        let mut graph = PortGraph::new();
+        graph.add_port(us_port1);
+        graph.add_port(us_port2);
+        graph.add_port(mexico_port);
+        graph.add_undirected_connection(PortID(1), PortID(2));
+        graph.add_undirected_connection(PortID(1), PortID(3));
+        graph.add_undirected_connection(PortID(2), PortID(3));
+
+        let mut sim: Simulation<PopulatedArea, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(0.7));
+        let total = sim.statistics.in_transit + sim.statistics.region_population;
+        for _ in 0..30 {
+            sim.update();
+            assert_eq!(sim.statistics.in_transit + sim.statistics.region_population, total);
+            // densities must follow populations as people come and go
+            for region in sim.geography.get_regions() {
+                let area = &region.population;
+                assert_eq!(area.total_density(), area.get_population().get_total() as f32/area.get_area());
+                assert_eq!(area.alive_density(), area.get_population().get_alive() as f32/area.get_area());
+            }
+        }
+    }
+
+    #[test]
+    /** Tests that jobs finishing in the same region on the same tick are deposited together */
+    fn test_batched_job_completion() {
+        let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        us.add_port(PortID(1), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        mexico.add_port(PortID(2), 300, Point2D::new(0.0, 2.0)).unwrap();
+        let (us_id, mexico_id) = (us.id(), mexico.id());
+
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![us, mexico]), RandomTransportAllocator::new(1.0));
+        let make_job = |population: Population, time: u32| TransportJob {job_id: None, start_port: PortID(1), start_region: us_id, end_port: PortID(2), end_region: mexico_id, population, time};
+        let arriving = [
+            Population {healthy: 100, infected: 5, dead: 0, recovered: 1},
+            Population {healthy: 30, infected: 0, dead: 0, recovered: 7},
+            Population {healthy: 2, infected: 20, dead: 0, recovered: 0}
+        ];
+        for (job_id, population) in arriving.iter().enumerate() {
+            sim.ongoing_transport.push(InProgressJob::new(make_job(*population, 0), job_id as u64));
+        }
+        // still travelling
+        sim.ongoing_transport.push(InProgressJob::new(make_job(Population::new_healthy(50), 2), 3));
+        sim.update_statistics();
+
+        let finished = sim.finish_jobs();
+        assert_eq!(finished.len(), 3);
+        let deposits = sim.deposit_arrivals(&finished);
+        let expected: Population = arriving.iter().sum();
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[&mexico_id], expected);
+        assert_eq!(*sim.geography.get_population(mexico_id).unwrap(), Population::new_healthy(3000) + expected);
+        assert_eq!(*sim.geography.get_population(us_id).unwrap(), Population::new_healthy(5000));
+        assert_eq!(sim.ongoing_transport.len(), 1);
+        assert!(sim.statistics_consistent());
+    }
+
+    #[test]
+    /** Tests that simulations seeded identically are in identical states on every tick */
+    fn test_state_hash() {
+        let run = |seed: u64| {
+            seed_random(seed);
+            let mut us = Region::new("United States".to_owned(), Population::new_random(5000));
+            let us_port1 = us.add_port(PortID(3), 100, Point2D::new(0.0, 0.0)).unwrap();
+            let us_port2 = us.add_port(PortID(1), 200, Point2D::new(5.0, 0.0)).unwrap();
+            let mut mexico = Region::new("Mexico".to_owned(), Population::new_random(3000));
+            let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 8.0)).unwrap();
+
+            let graph = PortGraph::from_edges(vec![us_port1, us_port2, mexico_port], vec![(PortID(1), PortID(2)), (PortID(2), PortID(3)), (PortID(3), PortID(1))]).unwrap();
+            let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![mexico, us]), RandomTransportAllocator::new(0.5));
+            let mut hashes = vec![sim.state_hash()];
+            for _ in 0..20 {
+                sim.update();
+                hashes.push(sim.state_hash());
+            }
+            assert_eq!(sim.tick(), 20);
+            (sim, hashes)
+        };
+
+        let (mut sim, first_run) = run(11);
+        assert_eq!(first_run, run(11).1);
+        // runs on the same tick are told apart by their populations and jobs
+        assert!(first_run.iter().zip(run(12).1).all(|(first, second)| *first != second));
+
+        // changing a population without updating changes the hash
+        let before = sim.state_hash();
+        let region_id = sim.geography.get_region_ids()[0];
+        sim.with_region_mut(region_id, |region| region.population.healthy += 1).unwrap();
+        assert_ne!(sim.state_hash(), before);
+    }
+
+    #[test]
+    /** Tests that flow matrix records every departing job */
+    fn test_flow_matrix() {
+        let (us, mexico) = (RegionID(1), RegionID(2));
+        let mut flow_matrix = FlowMatrix::default();
+        flow_matrix.record(us, mexico, Population {healthy: 10, infected: 5, dead: 0, recovered: 0});
+        flow_matrix.record(us, mexico, Population::new_healthy(20));
+        flow_matrix.record(mexico, us, Population::new_healthy(7));
+        flow_matrix.record(mexico, mexico, Population::default());
+        assert_eq!(flow_matrix.flow(us, mexico), 35);
+        assert_eq!(flow_matrix.flow(mexico, us), 7);
+        assert_eq!(flow_matrix.flow(us, us), 0);
+        assert_eq!(flow_matrix.to_csv(), "from,to,people\n1,2,35\n2,1,7\n");
+
+        let mut us = Region::new("United States".to_owned(), Population::new_random(5000));
+        let us_port1 = us.add_port(PortID(1), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let us_port2 = us.add_port(PortID(2), 200, Point2D::new(3.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_random(3000));
+        let mexico_port = mexico.add_port(PortID(3), 300, Point2D::new(0.0, 2.0)).unwrap();
+        let (us_id, mexico_id) = (us.id(), mexico.id());
+
+        let mut graph = PortGraph::new();
+        graph.add_port(us_port1);
+        graph.add_port(us_port2);
+        graph.add_port(mexico_port);
+        graph.add_undirected_connection(PortID(1), PortID(2));
+        graph.add_undirected_connection(PortID(1), PortID(3));
+        graph.add_undirected_connection(PortID(2), PortID(3));
+
+        // every job can be seen right after the update that created it
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(0.7));
+        let mut seen_ids: Vec<u64> = vec![];
+        let mut expected_flows: HashMap<(RegionID, RegionID), u64> = HashMap::new();
+        for _ in 0..30 {
+            sim.update();
+            for job in &sim.ongoing_transport {
+                if !seen_ids.contains(&job.job_id()) {
+                    seen_ids.push(job.job_id());
+                    *expected_flows.entry((job.job.start_region, job.job.end_region)).or_default() += job.job.population.get_total_u64();
+                }
+            }
+        }
+        for from in [us_id, mexico_id] {
+            for to in [us_id, mexico_id] {
+                assert_eq!(sim.flow_matrix.flow(from, to), expected_flows.get(&(from, to)).copied().unwrap_or_default());
+            }
+        }
+    }
+
+    // records total population arriving each tick
+    struct ArrivalRecorder {
+        batches: Rc<RefCell<Vec<(u32, usize, Population)>>>
+    }
+
+    impl SimulationObserver for ArrivalRecorder {
+        fn on_tick_end(&mut self, tick: u32, arrivals: &[TransportJob]) {
+            self.batches.borrow_mut().push((tick, arrivals.len(), arrivals.iter().map(|job| job.population).sum()));
+        }
+    }
+
+    // relies on default implementation
+    struct SilentObserver;
+
+    impl SimulationObserver for SilentObserver {}
+
+    #[test]
+    /** Tests that observers receive every job arriving during each tick together */
+    fn test_observer_tick_end() {
+        let us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        let mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        let (us_id, mexico_id) = (us.id(), mexico.id());
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![us, mexico]), RandomTransportAllocator::new(1.0));
+
+        let make_job = |population: Population, time: u32| TransportJob {job_id: None, start_port: PortID(1), start_region: us_id, end_port: PortID(2), end_region: mexico_id, population, time};
+        sim.ongoing_transport.push(InProgressJob::new(make_job(Population::new_healthy(10), 0), 0));
+        sim.ongoing_transport.push(InProgressJob::new(make_job(Population::new_healthy(20), 0), 1));
+        sim.ongoing_transport.push(InProgressJob::new(make_job(Population::new_healthy(5), 1), 2));
+        sim.update_statistics();
+
+        let batches = Rc::new(RefCell::new(vec![]));
+        sim.add_observer(Box::new(ArrivalRecorder {batches: Rc::clone(&batches)}));
+        sim.add_observer(Box::new(SilentObserver));
+        sim.run_until(3, |_| false);
+
+        assert_eq!(*batches.borrow(), vec![
+            (0, 2, Population::new_healthy(30)),
+            (1, 1, Population::new_healthy(5)),
+            (2, 0, Population::default())
+        ]);
+    }
+
+    #[test]
+    /** Tests that scheduled port changes happen exactly on their tick */
+    fn test_closure_schedule() {
+        let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+        let us_port = us.add_port(PortID(1), 100, Point2D::new(0.0, 0.0)).unwrap();
+        let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+        let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 1.0)).unwrap();
+        let graph = PortGraph::from_edges(vec![us_port, mexico_port], vec![(PortID(1), PortID(2))]).unwrap();
+
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), RandomTransportAllocator::new(1.0));
+        sim.schedule_port_status(3, PortID(2), PortStatus::Closed).unwrap();
+        sim.schedule_port_status(6, PortID(2), PortStatus::Open).unwrap();
+        assert!(sim.schedule_port_status(3, PortID(9), PortStatus::Closed).is_err());
+
+        for tick in 0..8 {
+            sim.update();
+            let expected = if (3..6).contains(&tick) { PortStatus::Closed } else { PortStatus::Open };
+            assert_eq!(sim.geography.get_port(PortID(2)).unwrap().port_status(), expected);
+            // no jobs leave for closed port, and jobs created this tick have not progressed yet
+            if expected == PortStatus::Closed {
+                assert!(sim.ongoing_transport.iter().all(|job| job.job.time < job.expected_time));
+            }
+        }
+        assert_eq!(sim.geography.get_port(PortID(2)).unwrap().status_history(), vec![(3, PortStatus::Closed), (6, PortStatus::Open)]);
+
+        // ticks that have passed can no longer be scheduled, but the upcoming one can
+        assert!(sim.schedule_port_status(7, PortID(2), PortStatus::Closed).is_err());
+        assert!(sim.schedule_port_status(0, PortID(2), PortStatus::Closed).is_err());
+        sim.schedule_port_status(8, PortID(2), PortStatus::Closed).unwrap();
+        sim.update();
+        assert_eq!(sim.geography.get_port(PortID(2)).unwrap().port_status(), PortStatus::Closed);
+    }
+
+    #[test]
+    /** Tests that smaller time steps make the pathogen take proportionally more updates to spread */
+    fn test_fractional_time_step_pathogen() {
+        let updates_to_spread = |dt: f64| {
+            seed_random(5);
+            let china = Region::new("China".to_owned(), Population {healthy: 1_000_000, infected: 1000, dead: 0, recovered: 0});
+            let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![china]), RandomTransportAllocator::new(0.0));
+            sim.set_dt(dt).unwrap();
+            sim.set_pathogen(PathogenStruct::new("Flu".to_owned(), 0.05, 0.0).unwrap());
+            sim.run_until(1000, |sim| sim.statistics.region_population.infected >= 50_000)
+        };
+
+        let (full_step, half_step) = (updates_to_spread(1.0), updates_to_spread(0.5));
+        assert!(full_step < 1000);
+        let ratio = half_step as f64/full_step as f64;
+        assert!((1.8..=2.2).contains(&ratio), "{full_step} updates with dt 1, {half_step} with dt 0.5");
+    }
+
+    #[test]
+    /** Tests that smaller time steps make jobs take proportionally more updates to travel */
+    fn test_fractional_time_step() {
+        let updates_to_arrive = |dt: f64| {
+            let us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+            let mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+            let (us_id, mexico_id) = (us.id(), mexico.id());
+            let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![us, mexico]), RandomTransportAllocator::new(1.0));
+            sim.set_dt(dt).unwrap();
+            let job = TransportJob {job_id: None, start_port: PortID(1), start_region: us_id, end_port: PortID(2), end_region: mexico_id, population: Population::new_healthy(10), time: 4};
+            sim.ongoing_transport.push(InProgressJob::new(job, 0));
+            sim.update_statistics();
+            sim.run_until(100, |sim| sim.ongoing_transport.is_empty())
+        };
+
+        // jobs spend an extra update arriving after they finish travelling
+        assert_eq!(updates_to_arrive(1.0), 5);
+        assert_eq!(updates_to_arrive(0.5), 9);
+        assert_eq!(updates_to_arrive(0.5) - 1, 2*(updates_to_arrive(1.0) - 1));
+
+        let china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let mut sim: Simulation<Population, RandomTransportAllocator> = Simulation::new(SimulationGeography::new(PortGraph::new(), vec![china]), RandomTransportAllocator::new(1.0));
+        assert!(sim.set_dt(0.0).is_err());
+        assert!(sim.set_dt(f64::NAN).is_err());
+        assert_eq!(sim.dt(), 1.0);
+    }
+
+    #[test]
+    fn test_time_step_scales_departures_and_immunity() {
+        seed_random(7);
+        let departures_over_20_ticks = |dt: f64| {
+            let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+            let us_port = us.add_port(PortID(1), 100, Point2D::default()).unwrap();
+            let mut mexico = Region::new("Mexico".to_owned(), Population::new_healthy(3000));
+            let mexico_port = mexico.add_port(PortID(2), 100, Point2D::new(10.0, 0.0)).unwrap();
+            let (us_id, mexico_id) = (us.id(), mexico.id());
+            let mut graph = PortGraph::new();
+            graph.add_port(us_port);
+            graph.add_port(mexico_port);
+            graph.add_undirected_connection(PortID(1), PortID(2));
+
+            let mut sim = Simulation::new(SimulationGeography::new(graph, vec![us, mexico]), ScheduledFlightAllocator {passengers: 15});
+            sim.set_dt(dt).unwrap();
+            for _ in 0..(20.0/dt) as u32 {
+                sim.update();
+            }
+            sim.flow_matrix.flow(us_id, mexico_id)
+        };
+        assert_eq!(departures_over_20_ticks(1.0), 300);
+        for dt in [0.5, 0.25] {
+            let departures = departures_over_20_ticks(dt);
+            assert!((280..=320).contains(&departures), "{departures} departures with dt {dt}");
+        }
+
+        let ticks_until_immunity_lost = |dt: f64| {
+            let population = TemporaryImmunityPopulation::new(Population {healthy: 50, infected: 0, dead: 0, recovered: 30}, 2).unwrap();
+            let geography = SimulationGeography::new(PortGraph::new(), vec![Region::new("Atlantis".to_owned(), population)]);
+            let mut sim = Simulation::new(geography, RandomTransportAllocator::new(0.0));
+            sim.set_dt(dt).unwrap();
+            let updates = sim.run_until(100, |sim| sim.statistics.region_population.recovered == 0);
+            f64::from(updates)*dt
+        };
+        assert_eq!(ticks_until_immunity_lost(1.0), 2.0);
+        assert_eq!(ticks_until_immunity_lost(0.5), 2.0);
+        assert_eq!(ticks_until_immunity_lost(0.25), 2.0);
+    }
+}
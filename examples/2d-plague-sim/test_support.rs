@@ -0,0 +1,25 @@
+// Shared scenarios for simulation tests
+
+use functionality::{math_utils::seed_random, point::Point2D, population_types::population::Population, region::{PortID, Region}, simulation_geography::SimulationGeography, transportation_allocator::RandomTransportAllocator, transportation_graph::PortGraph};
+
+/** Seed that small_world seeds the random number generator with */
+pub const SMALL_WORLD_SEED: u64 = 42;
+
+/** Creates a deterministic scenario of three regions connected both within and between regions */
+/** Seeds the random number generator, so that simulations of the returned scenario are reproducible */
+pub fn small_world() -> (SimulationGeography<Population>, RandomTransportAllocator) {
+    seed_random(SMALL_WORLD_SEED);
+    let mut us = Region::new("United States".to_owned(), Population::new_healthy(5000));
+    let us_port1 = us.add_port(PortID(0), 300, Point2D::new(0.0, 0.0)).unwrap();
+    let us_port2 = us.add_port(PortID(1), 200, Point2D::new(4.0, 0.0)).unwrap();
+    let mut mexico = Region::new("Mexico".to_owned(), Population {healthy: 2900, infected: 100, dead: 0, recovered: 0});
+    let mexico_port = mexico.add_port(PortID(2), 300, Point2D::new(0.0, 6.0)).unwrap();
+    let mut china = Region::new("China".to_owned(), Population::new_healthy(8000));
+    let china_port = china.add_port(PortID(3), 500, Point2D::new(20.0, 10.0)).unwrap();
+
+    let mut graph = PortGraph::from_edges(vec![us_port1, us_port2, mexico_port, china_port], vec![]).unwrap();
+    for (first, second) in [(0, 1), (0, 2), (1, 2), (0, 3), (2, 3)] {
+        graph.add_undirected_connection(PortID(first), PortID(second)).unwrap();
+    }
+    (SimulationGeography::new(graph, vec![us, mexico, china]), RandomTransportAllocator::new(0.5))
+}
@@ -19,20 +19,20 @@ fn main() {
     // }   
 
     let mut us: Region = Region::new("North America".to_owned(), Population::new_healthy(5000));
-    let us_port_top_left = us.add_port(PortID(0), 500, Point2D::new(50.0, 50.0));
-    let us_port_top_right = us.add_port(PortID(1), 500, Point2D::new(100.0, 50.0));
-    let us_port_bottom_left = us.add_port(PortID(2), 500, Point2D::new(100.0, 50.0));
-    let us_port_bottom_right = us.add_port(PortID(3), 500, Point2D::new(100.0, 100.0));
+    let us_port_top_left = us.add_port(PortID(0), 500, Point2D::new(50.0, 50.0)).unwrap();
+    let us_port_top_right = us.add_port(PortID(1), 500, Point2D::new(100.0, 50.0)).unwrap();
+    let us_port_bottom_left = us.add_port(PortID(2), 500, Point2D::new(100.0, 50.0)).unwrap();
+    let us_port_bottom_right = us.add_port(PortID(3), 500, Point2D::new(100.0, 100.0)).unwrap();
 
     let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(3000));
-    let brasil_port_top_left = brazil.add_port(PortID(4), 700, Point2D::new(50.0, 170.0));
-    let brasil_port_top_right = brazil.add_port(PortID(5), 1000, Point2D::new(150.0, 210.0));
+    let brasil_port_top_left = brazil.add_port(PortID(4), 700, Point2D::new(50.0, 170.0)).unwrap();
+    let brasil_port_top_right = brazil.add_port(PortID(5), 1000, Point2D::new(150.0, 210.0)).unwrap();
 
     let mut asia: Region = Region::new("Asia".to_owned(), Population::new_healthy(30000));
-    let asia_port = asia.add_port(PortID(6), 5000, Point2D::new(400.0, 50.0));
+    let asia_port = asia.add_port(PortID(6), 5000, Point2D::new(400.0, 50.0)).unwrap();
 
     let mut africa: Region = Region::new("Africa".to_owned(), Population::new_healthy(20000));
-    let africa_port = africa.add_port(PortID(7), 5000, Point2D::new(300.0, 300.0));
+    let africa_port = africa.add_port(PortID(7), 5000, Point2D::new(300.0, 300.0)).unwrap();
 
     let mut graph: PortGraph = PortGraph::new();
     graph.add_port(us_port_bottom_left);
@@ -1,32 +1,102 @@
-use std::{error::Error, fs, path::Path};
+use std::{collections::HashSet, error::Error, fs, path::Path};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{point::{Point2D}, population_types::{population::Population, PopulationType}, region::{Port, PortID, Region}, transportation_graph::PortGraph};
 
+/** Version of configuration data written by this code */
+pub const CONFIG_VERSION: u32 = 1;
+
 /** Responsible for holding configuration data of plague simulation */
 #[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ConfigData <P = Population> where P: PopulationType{
+    /** Format version of the data, files without one are version 0 */
+    #[serde(default)]
+    pub version: u32,
     pub regions: Vec<Region<P>>,
     pub graph: PortGraph
 }
 
 impl <P> ConfigData <P> where P: PopulationType {
     pub fn new(regions: Vec<Region<P>>, graph: PortGraph) -> Self{
-        Self { regions, graph}
+        Self { version: CONFIG_VERSION, regions, graph}
     }
 }
 
+#[cfg(feature = "schema")]
+impl <P> ConfigData <P> where P: PopulationType + schemars::JsonSchema {
+    /** Generates JSON schema that configuration data files must follow */
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(ConfigData<P>);
+        serde_json::to_string_pretty(&schema).unwrap()
+    }
+}
 
+
+/** Loads configuration data from a JSON file, upgrading data written by older versions */
+/** Fails if file cannot be read, is not valid configuration data or is newer than this code */
 pub fn load_config_data<P>(config_data_path: P) -> Result<ConfigData, Box<dyn Error>> where P: AsRef<Path> {
     let regions_data = fs::read_to_string(config_data_path)?;
-    let json: ConfigData<Population> = serde_json::from_str(&regions_data)?;
-    Ok(json)
+    let mut json: Value = serde_json::from_str(&regions_data)?;
+    migrate_config(&mut json)?;
+    let config: ConfigData<Population> = serde_json::from_value(json)?;
+    Ok(config)
+}
+
+/** Upgrades raw configuration data in place to the current version, one version at a time */
+fn migrate_config(json: &mut Value) -> Result<(), String> {
+    let version = match json.get("version") {
+        None => 0,
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(format!("Config version must be a non-negative integer, not {}", version))?
+    };
+    if version > CONFIG_VERSION {
+        return Err(format!("Config version {} is newer than supported version {}", version, CONFIG_VERSION));
+    }
+    if version < 1 {
+        migrate_v0_to_v1(json);
+    }
+    json["version"] = Value::from(CONFIG_VERSION);
+    Ok(())
+}
+
+// version 0 ports stored whether they were closed instead of a status,
+// and version 0 regions could list the same port more than once
+fn migrate_v0_to_v1(json: &mut Value) {
+    let migrate_port = |port: &mut Value| {
+        if let Some(port) = port.as_object_mut() {
+            if let Some(closed) = port.remove("closed") {
+                let status = if closed.as_bool() == Some(true) { "Closed" } else { "Open" };
+                port.entry("status").or_insert(Value::from(status));
+            }
+        }
+    };
+    if let Some(regions) = json.get_mut("regions").and_then(Value::as_array_mut) {
+        for region in regions {
+            if let Some(ports) = region.get_mut("ports").and_then(Value::as_array_mut) {
+                ports.iter_mut().for_each(migrate_port);
+                let mut seen_ids = HashSet::new();
+                ports.retain(|port| seen_ids.insert(port.get("id").cloned().unwrap_or(Value::Null).to_string()));
+            }
+        }
+    }
+    if let Some(nodes) = json.pointer_mut("/graph/port_nodes").and_then(Value::as_object_mut) {
+        for node in nodes.values_mut() {
+            if let Some(port) = node.get_mut("port") {
+                migrate_port(port);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{config::{load_config_data, ConfigData}, point::Point2D, population_types::population::Population, region::PortID};
+    use serde_json::json;
+
+    use crate::{config::{load_config_data, migrate_config, ConfigData, CONFIG_VERSION}, point::Point2D, population_types::population::Population, region::{PortID, PortStatus}};
 
 
     #[test]
@@ -74,5 +144,47 @@ mod tests {
         assert_eq!(graph.get_dest_ports(PortID(5)).unwrap(), vec![graph.get_port(PortID(0)).unwrap()]);
               
     }
-}
 
+    #[test]
+    fn test_config_migration() {
+        // data file predates versioning
+        let config_data = load_config_data("test_data/data.json").unwrap();
+        assert_eq!(config_data.version, CONFIG_VERSION);
+        for region in &config_data.regions {
+            assert_eq!(region.get_ports().len(), 2);
+            assert_eq!(region.get_tag("continent"), None);
+            for port in region.get_ports() {
+                assert_eq!(port.port_status(), PortStatus::Open);
+                assert!(port.status_history().is_empty());
+                assert!(!port.has_screening);
+            }
+        }
+        assert!(config_data.graph.iter_ports().all(|port| port.port_status() == PortStatus::Open));
+
+        let mut v0 = json!({"regions": [{"id": 0, "name": "Atlantis", "population": {"healthy": 10, "infected": 0, "dead": 0, "recovered": 0},
+            "ports": [{"capacity": 5, "closed": true, "region": 0, "id": 0, "pos": {"x": 0.0, "y": 0.0}}]}],
+            "graph": {"port_nodes": {}}});
+        migrate_config(&mut v0).unwrap();
+        let config_data: ConfigData = serde_json::from_value(v0).unwrap();
+        assert_eq!(config_data.regions[0].get_port(PortID(0)).unwrap().port_status(), PortStatus::Closed);
+
+        // current data is unchanged by migration
+        let config_data = ConfigData::new(config_data.regions, config_data.graph);
+        let mut current = serde_json::to_value(&config_data).unwrap();
+        let expected = current.clone();
+        migrate_config(&mut current).unwrap();
+        assert_eq!(current, expected);
+
+        assert!(migrate_config(&mut json!({"version": CONFIG_VERSION + 1})).is_err());
+        assert!(migrate_config(&mut json!({"version": "one"})).is_err());
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_json_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&ConfigData::<Population>::json_schema()).unwrap();
+        let properties = &schema["properties"];
+        assert!(properties.get("regions").is_some());
+        assert!(properties.get("graph").is_some());
+    }
+}
@@ -6,9 +6,12 @@ pub mod pathogen;
 pub mod population_types;
 pub mod config;
 pub mod transportation_allocator;
+pub mod travel_cost;
 pub mod math_utils;
 pub mod point;
 pub mod simulation_geography;
+pub mod port_spatial_index;
+pub mod prelude;
 
 
 #[cfg(test)]
@@ -24,16 +27,16 @@ mod tests {
         // create countries
         let mut us = Region::new("United States".to_string(), Population::new_healthy(1000));
         let mut us_ports = vec![];
-        let us_port1 = us.add_port(PortID::new(0), 100, Point2D::default());
-        let us_port2 = us.add_port(PortID::new(1), 200, Point2D::default());
+        let us_port1 = us.add_port(PortID::new(0), 100, Point2D::default()).unwrap();
+        let us_port2 = us.add_port(PortID::new(1), 200, Point2D::default()).unwrap();
         us_ports.push(us_port1);
         us_ports.push(us_port2);
 
         let mut china = Region::new("China".to_string(), Population::new_healthy(10000));
         let mut china_ports = vec![];
-        let china_port1 = china.add_port(PortID::new(2), 100, Point2D::default());
-        let china_port2 = china.add_port(PortID::new(3), 200, Point2D::default());
-        let china_port3 = china.add_port(PortID::new(4), 200, Point2D::default());
+        let china_port1 = china.add_port(PortID::new(2), 100, Point2D::default()).unwrap();
+        let china_port2 = china.add_port(PortID::new(3), 200, Point2D::default()).unwrap();
+        let china_port3 = china.add_port(PortID::new(4), 200, Point2D::default()).unwrap();
         china_ports.push(china_port1);
         china_ports.push(china_port2);
         china_ports.push(china_port3);
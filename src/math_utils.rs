@@ -1,24 +1,76 @@
 use rand_distr::{Binomial, Distribution};
-use rand::{rngs::ThreadRng, Rng};
+use rand::{rngs::{StdRng, ThreadRng}, Rng, SeedableRng};
+use std::cell::RefCell;
 
 
 /* Provides important math functionality */
 
+thread_local! {
+    // generator for distributions fastrand cannot sample, seeded alongside fastrand so results are reproducible
+    static DISTRIBUTION_RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(fastrand::u64(..)));
+}
+
+/// Seeds the random number generator of the current thread
+/// 
+/// All random values subsequently generated on this thread by this module are reproducible
+pub fn seed_random(seed: u64) {
+    // distribution generator first, since creating it may draw from fastrand
+    DISTRIBUTION_RNG.with(|generator| *generator.borrow_mut() = StdRng::seed_from_u64(seed));
+    fastrand::seed(seed);
+}
 
 // generate a random float from 0 to 1 (noninclusive)
 pub fn get_random() -> f64 {
     fastrand::f64()
 }
 
+/// Generates a random integer from low to high, both inclusive
+/// 
+/// Reproducible after seeding with seed_random
+/// # Panics
+/// * Panics if low is greater than high
+pub fn random_u32_in(low: u32, high: u32) -> u32 {
+    fastrand::u32(low..=high)
+}
+
 pub fn pick_random<I>(collection: I) -> Option<<I as IntoIterator>::Item> where I: IntoIterator , <I as IntoIterator>::IntoIter: ExactSizeIterator{
    fastrand::choice(collection)
 }
 
+/// Picks index of a weight with probability proportional to that weight
+/// 
+/// Returns None if weights sum to 0 or overflow
+/// 
+/// Reproducible after seeding with seed_random
+pub fn pick_weighted(weights: &[u64]) -> Option<usize> {
+    let total = weights.iter().try_fold(0_u64, |sum, weight| sum.checked_add(*weight))?;
+    if total == 0 {
+        return None;
+    }
+    let mut remaining = fastrand::u64(0..total);
+    for (index, weight) in weights.iter().enumerate() {
+        if remaining < *weight {
+            return Some(index);
+        }
+        remaining = remaining.saturating_sub(*weight);
+    }
+    None
+}
+
 /// Returns how many trials succeeded given a trial amount and a success rate according to a binomial distribution
 pub fn binomial_sample(trials: u32, success_rate: f64) -> u32 {
     let distr = Binomial::new(trials.into(), success_rate).unwrap();
-    let mut generator = rand::thread_rng();
-    distr.sample(&mut generator).try_into().unwrap()
+    DISTRIBUTION_RNG.with(|generator| distr.sample(&mut *generator.borrow_mut())).try_into().unwrap()
+}
+
+/// Converts chance of something happening during one tick into the chance of it happening during dt ticks, as 1-(1-p)^dt
+/// 
+/// Returns chance unchanged when dt is 1
+pub fn chance_over(chance: f64, dt: f64) -> f64 {
+    if dt == 1.0_f64 {
+        return chance;
+    }
+    1.0_f64 - (1.0_f64 - chance).powf(dt)
 }
 
 /// Rounds down or up to nearest integer randomly
@@ -79,5 +131,53 @@ mod tests {
 
         assert_eq!(values.len(), 4);
     }
+
+    #[test]
+    fn seed_random() {
+        math_utils::seed_random(42);
+        let first_run: Vec<(f64, u32)> = (0..10).map(|_| (math_utils::get_random(), math_utils::binomial_sample(100, 0.5))).collect();
+        math_utils::seed_random(42);
+        let second_run: Vec<(f64, u32)> = (0..10).map(|_| (math_utils::get_random(), math_utils::binomial_sample(100, 0.5))).collect();
+        assert_eq!(first_run, second_run);
+    }
+
+    #[test]
+    fn random_u32_in() {
+        for (low, high) in [(0, 0), (0, 1), (5, 17), (100, 100), (0, u32::MAX), (u32::MAX - 3, u32::MAX)] {
+            for _ in 0..1000 {
+                let value = math_utils::random_u32_in(low, high);
+                assert!((low..=high).contains(&value));
+            }
+        }
+        // both ends of range can be drawn
+        let draws: Vec<u32> = (0..1000).map(|_| math_utils::random_u32_in(3, 4)).collect();
+        assert!(draws.contains(&3) && draws.contains(&4));
+    }
+
+    #[test]
+    fn chance_over() {
+        assert_eq!(math_utils::chance_over(0.1, 1.0), 0.1);
+        assert!((math_utils::chance_over(0.19, 0.5) - 0.1).abs() < 1e-12);
+        assert!((math_utils::chance_over(0.5, 2.0) - 0.75).abs() < 1e-12);
+        assert_eq!(math_utils::chance_over(0.0, 0.5), 0.0);
+        assert_eq!(math_utils::chance_over(1.0, 0.5), 1.0);
+    }
+
+    #[test]
+    fn pick_weighted() {
+        assert_eq!(math_utils::pick_weighted(&[]), None);
+        assert_eq!(math_utils::pick_weighted(&[0, 0]), None);
+        assert_eq!(math_utils::pick_weighted(&[u64::MAX, 1]), None);
+        assert_eq!(math_utils::pick_weighted(&[0, 5, 0]), Some(1));
+
+        math_utils::seed_random(7);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[math_utils::pick_weighted(&[1, 0, 3]).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((2000..3000).contains(&counts[0]));
+        assert!((7000..8000).contains(&counts[2]));
+    }
     
 }
\ No newline at end of file
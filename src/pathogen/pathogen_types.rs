@@ -1 +1,3 @@
-pub mod pathogen;
\ No newline at end of file
+pub mod pathogen;
+pub mod spontaneous_pathogen;
+pub mod threshold_pathogen;
\ No newline at end of file
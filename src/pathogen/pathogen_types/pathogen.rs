@@ -1,8 +1,37 @@
-use crate::{math_utils::get_random, population_types::{population::Population, Density, PopulationType}};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::collections::HashMap;
+
+use crate::{math_utils::{binomial_sample, chance_over, get_random}, population_types::{population::Population, Density, PopulationType}, region::RegionID, simulation_geography::SimulationGeography};
 
 // Represents a pathogen, which are entities that transform populations without removing people from, or adding people to them
 pub trait Pathogen {
     fn calculate_population<T>(&self, population: T) -> T where T: PopulationType;
+
+    /// Acts on population over dt ticks, which may be a fraction of a tick, so that pathogens keep pace with travel when simulations use a different time step
+    /// 
+    /// By default acts once for every whole tick, then once more with a chance of the remaining fraction
+    /// 
+    /// Pathogens with per-tick chances should instead scale them with chance_over
+    fn calculate_population_over<T>(&self, population: T, dt: f64) -> T where T: PopulationType {
+        let mut output_population = population;
+        for _ in 0..(dt.trunc() as u64) {
+            output_population = self.calculate_population(output_population);
+        }
+        if get_random() < dt.fract() {
+            output_population = self.calculate_population(output_population);
+        }
+        output_population
+    }
+
+    /// Called once per tick after the pathogen has acted on every region, so pathogens can act on the whole geography
+    /// 
+    /// Returns populations before and after of every region whose population was changed
+    /// 
+    /// Does nothing by default
+    fn end_tick<P>(&self, _geography: &mut SimulationGeography<P>, _tick: u32) -> HashMap<RegionID, (Population, Population)> where P: PopulationType {
+        HashMap::new()
+    }
 }
 
 // Represents a disease that can spread from person to person
@@ -27,4 +56,111 @@ impl PathogenStruct {
 
         Ok(Self {name, infectivity, lethality})
     }
-}
\ No newline at end of file
+
+    // creates pathogen with infectivity and lethality drawn uniformly from 0 to 1
+    // the same seed always creates the same pathogen
+    pub fn random(rng_seed: u64) -> Self {
+        let mut generator = StdRng::seed_from_u64(rng_seed);
+        let infectivity = generator.gen_range(0.0_f64..=1.0_f64);
+        let lethality = generator.gen_range(0.0_f64..=1.0_f64);
+        Self {name: format!("Random pathogen {rng_seed}"), infectivity, lethality}
+    }
+}
+
+/// Each tick, healthy people are infected with a chance of infectivity scaled by the fraction of alive people that are infected
+/// 
+/// Over dt ticks, each of these chances p becomes 1-(1-p)^dt
+/// 
+/// Deaths are drawn only from people infected at the start of the tick, each dying with a chance of lethality, so healthy people never die
+/// 
+/// People do not recover from this pathogen
+impl Pathogen for PathogenStruct {
+    fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+        self.calculate_population_over(population, 1.0_f64)
+    }
+
+    fn calculate_population_over<T>(&self, population: T, dt: f64) -> T where T: PopulationType {
+        let prev = population.population();
+        let alive = prev.get_alive();
+        if prev.infected == 0 || alive == 0 {
+            return population;
+        }
+        let infected_fraction = f64::from(prev.infected)/f64::from(alive);
+        let infection_chance = chance_over((self.infectivity*infected_fraction).clamp(0.0_f64, 1.0_f64), dt);
+        let new_infections = binomial_sample(prev.healthy, infection_chance);
+        let deaths = binomial_sample(prev.infected, chance_over(self.lethality, dt));
+        let new_population = Population {
+            healthy: prev.healthy.saturating_sub(new_infections),
+            infected: prev.infected.saturating_sub(deaths).saturating_add(new_infections),
+            dead: prev.dead.saturating_add(deaths),
+            recovered: prev.recovered
+        };
+        let mut output_population = population;
+        output_population.set_population(new_population);
+        output_population
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{math_utils::seed_random, pathogen::pathogen_types::pathogen::Pathogen, population_types::population::Population};
+
+    use super::PathogenStruct;
+
+    #[test]
+    fn random_pathogen() {
+        for seed in 0..200 {
+            let pathogen = PathogenStruct::random(seed);
+            assert_eq!(PathogenStruct::new(pathogen.name.clone(), pathogen.infectivity, pathogen.lethality), Ok(pathogen.clone()));
+            assert_eq!(PathogenStruct::random(seed), pathogen);
+        }
+        assert_ne!(PathogenStruct::random(1), PathogenStruct::random(2));
+    }
+
+    #[test]
+    fn deaths_only_among_infected() {
+        seed_random(8);
+        let deadly = PathogenStruct::new("Deadly".to_owned(), 1.0, 1.0).unwrap();
+        let healthy = Population {healthy: 10000, infected: 0, dead: 0, recovered: 500};
+        for _ in 0..20 {
+            assert_eq!(deadly.calculate_population(healthy), healthy);
+        }
+
+        // everyone infected at the start of the tick dies, but newly infected people do not
+        let population = Population {healthy: 9000, infected: 1000, dead: 0, recovered: 0};
+        let result = deadly.calculate_population(population);
+        assert_eq!(result.dead, 1000);
+        assert_eq!(result.get_total(), population.get_total());
+        assert_eq!(result.healthy + result.infected, 9000);
+        assert!(result.infected > 0);
+
+        let harmless = PathogenStruct::new("Harmless".to_owned(), 0.0, 0.0).unwrap();
+        assert_eq!(harmless.calculate_population(population), population);
+    }
+
+    #[test]
+    fn fractional_ticks() {
+        let pathogen = PathogenStruct::new("Flu".to_owned(), 0.2, 0.1).unwrap();
+        let population = Population {healthy: 500_000, infected: 500_000, dead: 0, recovered: 0};
+        let average = |dt: f64| {
+            seed_random(21);
+            let (mut infections, mut deaths) = (0_u64, 0_u64);
+            for _ in 0..20 {
+                let result = pathogen.calculate_population_over(population, dt);
+                assert_eq!(result.get_total(), population.get_total());
+                infections += u64::from(population.healthy - result.healthy);
+                deaths += u64::from(result.dead);
+            }
+            (infections as f64/20.0, deaths as f64/20.0)
+        };
+        // chance of infection is 0.1 per tick, and of dying 0.1 per tick
+        let (infections, deaths) = average(1.0);
+        assert!((infections/500_000.0 - 0.1).abs() < 0.005);
+        assert!((deaths/500_000.0 - 0.1).abs() < 0.005);
+        // half a tick gives chances of 1-(1-0.1)^0.5
+        let half_chance = 1.0 - 0.9_f64.sqrt();
+        let (infections, deaths) = average(0.5);
+        assert!((infections/500_000.0 - half_chance).abs() < 0.005);
+        assert!((deaths/500_000.0 - half_chance).abs() < 0.005);
+    }
+}
@@ -1,33 +1,159 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::{math_utils::{chance_over, get_random, pick_random}, population_types::{population::Population, PopulationType}, region::RegionID, simulation_geography::SimulationGeography};
+
+use super::pathogen::Pathogen;
+
 /// Represents a pathogen that can spontaneously spawn into populations without any infected individuals
 /// Spontaneous generation occurs only when the following conditions hold:
 /// * At least one healthy individual exists in the population
 /// * No infected individuals exist in the population
 /// * Random chance allows its creation
+/// 
+/// Over dt ticks, spawn_chance becomes 1-(1-spawn_chance)^dt
+/// 
+/// guaranteed_first_spawn is the tick by which the pathogen must have spawned somewhere, if set
+/// * See guarantee_first_spawn
 pub struct SpontaneousPathogen<T> where T: Pathogen {
     pub spawn_chance: f32,
-    pub pathogen: T
+    pub pathogen: T,
+    pub guaranteed_first_spawn: Option<u32>,
+    // whether the pathogen has spawned into any population yet
+    has_spawned: Cell<bool>
 }
 
 impl<T> SpontaneousPathogen<T> where T: Pathogen {
     pub fn new(spawn_chance: f32, pathogen: T) -> Self {
-        Self {spawn_chance, pathogen}
+        Self {spawn_chance, pathogen, guaranteed_first_spawn: None, has_spawned: Cell::new(false)}
+    }
+
+    /// Creates pathogen that is guaranteed to have spawned somewhere by the given tick
+    pub fn with_guaranteed_first_spawn(spawn_chance: f32, pathogen: T, deadline: u32) -> Self {
+        Self {guaranteed_first_spawn: Some(deadline), ..Self::new(spawn_chance, pathogen)}
+    }
+
+    /// Whether the pathogen has spawned into any population yet
+    pub fn has_spawned(&self) -> bool {
+        self.has_spawned.get()
+    }
+
+    /// Spawns a single infected person into a random region with healthy people, if nobody has been infected by the guaranteed first spawn tick
+    /// 
+    /// Should be called once per tick, after the pathogen has been applied to each region, which end_tick does
+    /// 
+    /// Returns ID of region spawned into, if any
+    pub fn guarantee_first_spawn<P>(&self, geography: &mut SimulationGeography<P>, tick: u32) -> Option<RegionID> where P: PopulationType {
+        self.spawn_if_overdue(geography, tick).map(|(region_id, _)| region_id)
+    }
+
+    // spawns as described by guarantee_first_spawn, returning ID of region spawned into along with its population before spawning
+    fn spawn_if_overdue<P>(&self, geography: &mut SimulationGeography<P>, tick: u32) -> Option<(RegionID, Population)> where P: PopulationType {
+        let deadline = self.guaranteed_first_spawn?;
+        if geography.get_regions().any(|region| region.population.population().infected > 0) {
+            self.has_spawned.set(true);
+        }
+        if self.has_spawned() || tick < deadline {
+            return None;
+        }
+        // regions are sorted so that spawns are reproducible
+        let mut candidates: Vec<RegionID> = geography.get_regions()
+            .filter(|region| region.population.population().healthy > 0)
+            .map(|region| region.id())
+            .collect();
+        candidates.sort();
+        let region_id = pick_random(candidates)?;
+        let old_population = geography.get_population(region_id)?.population();
+        geography.infect_region(region_id, 1).ok()?;
+        self.has_spawned.set(true);
+        Some((region_id, old_population))
     }
 }
 
 impl<P> Pathogen for SpontaneousPathogen<P> where P: Pathogen {
     fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+        self.calculate_population_over(population, 1.0_f64)
+    }
+
+    fn calculate_population_over<T>(&self, population: T, dt: f64) -> T where T: PopulationType {
         let prev_population = population.population();
         let new_population;
         // spontaneous generation 
-        if prev_population.healthy > 0 && prev_population.infected == 0 && get_random() as f32 <= self.spawn_chance {
+        if prev_population.healthy > 0 && prev_population.infected == 0 && get_random() <= chance_over(f64::from(self.spawn_chance), dt) {
             // spawn pathogen into population
             new_population = Population {healthy: prev_population.healthy - 1, infected: 1, dead: prev_population.dead, recovered: prev_population.recovered};
+            self.has_spawned.set(true);
         } else {
             // pathogen acts regularly
-            new_population = self.pathogen.calculate_population(prev_population);
+            new_population = self.pathogen.calculate_population_over(prev_population, dt);
         }
         let mut output_population = population;
         output_population.set_population(new_population);
         output_population
     }
+
+    fn end_tick<Q>(&self, geography: &mut SimulationGeography<Q>, tick: u32) -> HashMap<RegionID, (Population, Population)> where Q: PopulationType {
+        let mut changes = self.pathogen.end_tick(geography, tick);
+        if let Some((region_id, old_population)) = self.spawn_if_overdue(geography, tick) {
+            let new_population = geography.get_population(region_id).map_or(old_population, PopulationType::population);
+            let (first_population, _) = changes.remove(&region_id).unwrap_or((old_population, new_population));
+            changes.insert(region_id, (first_population, new_population));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pathogen::pathogen_types::pathogen::Pathogen, population_types::{population::Population, PopulationType}, region::Region, simulation_geography::SimulationGeography, transportation_graph::PortGraph};
+
+    use super::SpontaneousPathogen;
+
+    // leaves populations unchanged
+    struct InertPathogen;
+
+    impl Pathogen for InertPathogen {
+        fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+            population
+        }
+    }
+
+    #[test]
+    fn guaranteed_first_spawn() {
+        let regions = vec![
+            Region::new("China".to_owned(), Population::new_healthy(5000)),
+            Region::new("Benin".to_owned(), Population::new_healthy(3000)),
+            Region::new("Atlantis".to_owned(), Population {healthy: 0, infected: 0, dead: 100, recovered: 0}),
+        ];
+        let mut geography = SimulationGeography::new(PortGraph::new(), regions);
+        let pathogen = SpontaneousPathogen::with_guaranteed_first_spawn(1e-12, InertPathogen, 5);
+
+        let count_infected = |geography: &SimulationGeography<Population>| geography.get_regions().filter(|region| region.population.infected > 0).count();
+        for tick in 0..=5 {
+            for region_id in geography.get_region_ids() {
+                geography.with_region_mut(region_id, |region| {
+                    let new_population = pathogen.calculate_population(region.population);
+                    region.population.set_population(new_population);
+                }).unwrap();
+            }
+            let spawned_into = pathogen.guarantee_first_spawn(&mut geography, tick);
+            if tick < 5 {
+                assert!(spawned_into.is_none());
+            } else {
+                let spawned_into = spawned_into.unwrap();
+                assert_eq!(geography.get_population(spawned_into).unwrap().infected, 1);
+            }
+        }
+        assert!(pathogen.has_spawned());
+        assert_eq!(count_infected(&geography), 1);
+
+        // only the first spawn is guaranteed
+        for region_id in geography.get_region_ids() {
+            geography.with_region_mut(region_id, |region| region.population.infected = 0).unwrap();
+        }
+        assert!(pathogen.guarantee_first_spawn(&mut geography, 6).is_none());
+
+        // no guarantee by default
+        let mut geography = SimulationGeography::new(PortGraph::new(), vec![Region::new("China".to_owned(), Population::new_healthy(5000))]);
+        assert!(SpontaneousPathogen::new(0.0, InertPathogen).guarantee_first_spawn(&mut geography, 100).is_none());
+    }
 }
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use crate::{math_utils::{chance_over, get_random}, population_types::{population::Population, PopulationType}, region::RegionID, simulation_geography::SimulationGeography};
+
+use super::pathogen::Pathogen;
+
+/// Represents a pathogen that only spreads once enough people are infected, modelling stochastic fade-out of small outbreaks
+/// 
+/// While a population has at least min_infected infected individuals, the wrapped pathogen acts regularly
+/// 
+/// While a population has fewer infected individuals than that, but at least one:
+/// * The infection does not grow
+/// * With probability extinction_chance, the outbreak fades out and every infected individual recovers
+/// 
+/// Over dt ticks, extinction_chance becomes 1-(1-extinction_chance)^dt
+pub struct ThresholdPathogen<T> where T: Pathogen {
+    pub pathogen: T,
+    pub min_infected: u32,
+    pub extinction_chance: f64
+}
+
+impl<T> ThresholdPathogen<T> where T: Pathogen {
+    pub fn new(pathogen: T, min_infected: u32, extinction_chance: f64) -> Result<Self, String> {
+        if !(0.0_f64..=1.0_f64).contains(&extinction_chance) {
+            return Err(format!("Extinction chance must be between 0 and 1, not {extinction_chance}"));
+        }
+        Ok(Self {pathogen, min_infected, extinction_chance})
+    }
+}
+
+impl<T> Pathogen for ThresholdPathogen<T> where T: Pathogen {
+    fn calculate_population<P>(&self, population: P) -> P where P: PopulationType {
+        self.calculate_population_over(population, 1.0_f64)
+    }
+
+    fn calculate_population_over<P>(&self, population: P, dt: f64) -> P where P: PopulationType {
+        let prev_population = population.population();
+        if prev_population.infected == 0 || prev_population.infected >= self.min_infected {
+            return self.pathogen.calculate_population_over(population, dt);
+        }
+        if get_random() >= chance_over(self.extinction_chance, dt) {
+            // outbreak survives but does not grow
+            return population;
+        }
+        let new_population = Population {
+            infected: 0,
+            recovered: prev_population.recovered.saturating_add(prev_population.infected),
+            ..prev_population
+        };
+        let mut output_population = population;
+        output_population.set_population(new_population);
+        output_population
+    }
+
+    fn end_tick<P>(&self, geography: &mut SimulationGeography<P>, tick: u32) -> HashMap<RegionID, (Population, Population)> where P: PopulationType {
+        self.pathogen.end_tick(geography, tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{math_utils::seed_random, pathogen::pathogen_types::pathogen::Pathogen, population_types::{population::Population, PopulationType}};
+
+    use super::ThresholdPathogen;
+
+    // doubles the number of infected each tick
+    struct DoublingPathogen;
+
+    impl Pathogen for DoublingPathogen {
+        fn calculate_population<T>(&self, population: T) -> T where T: PopulationType {
+            let prev = population.population();
+            let newly_infected = prev.infected.min(prev.healthy);
+            let mut output = population;
+            output.set_population(Population {healthy: prev.healthy - newly_infected, infected: prev.infected + newly_infected, ..prev});
+            output
+        }
+    }
+
+    #[test]
+    fn threshold_pathogen() {
+        assert!(ThresholdPathogen::new(DoublingPathogen, 10, 1.5).is_err());
+        let pathogen = ThresholdPathogen::new(DoublingPathogen, 10, 0.5).unwrap();
+
+        // a single infected either fades out or stays, never grows
+        seed_random(11);
+        let single = Population {healthy: 1000, infected: 1, dead: 0, recovered: 0};
+        let mut faded = 0;
+        for _ in 0..200 {
+            let result = pathogen.calculate_population(single);
+            assert_eq!(result.get_total(), single.get_total());
+            if result.infected == 0 {
+                assert_eq!(result.recovered, 1);
+                faded += 1;
+            } else {
+                assert_eq!(result, single);
+            }
+        }
+        assert!(faded > 0 && faded < 200);
+
+        // a large seed always grows
+        let mut population = Population {healthy: 1000, infected: 10, dead: 0, recovered: 0};
+        for _ in 0..5 {
+            let result = pathogen.calculate_population(population);
+            assert!(result.infected > population.infected);
+            population = result;
+        }
+
+        // nothing happens without infected
+        assert_eq!(pathogen.calculate_population(Population::new_healthy(1000)), Population::new_healthy(1000));
+    }
+}
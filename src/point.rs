@@ -1,8 +1,11 @@
+use std::ops::{Add, Mul, Sub};
+
 use serde::{Deserialize, Serialize};
 
 /// Represents locations with a 2D Point
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Point2D {
     pub x: f64,
     pub y: f64
@@ -16,4 +19,198 @@ impl Point2D {
     pub fn distance(&self, second: &Self) -> f64 {
         f64::sqrt((self.x - second.x)*(self.x - second.x) + (self.y - second.y)*(self.y - second.y))
     }
-}
\ No newline at end of file
+
+    /// Multiplies both coordinates by factor, like multiplying by a scalar
+    pub fn scale(&self, factor: f64) -> Self {
+        Self {x: self.x*factor, y: self.y*factor}
+    }
+
+    /// Calculates distance from origin, treating point as a vector
+    pub fn magnitude(&self) -> f64 {
+        f64::sqrt(self.x*self.x + self.y*self.y)
+    }
+
+    /// Calculates angle in radians of the direction from this point to other, between -π and π
+    /// 
+    /// Measured counterclockwise from the positive x axis, so a point with greater y is at π/2
+    pub fn bearing(&self, other: &Self) -> f64 {
+        f64::atan2(other.y - self.y, other.x - self.x)
+    }
+
+    /// Whether both coordinates are finite, i.e. neither NaN nor infinite
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+
+    /// Calculates distance to second point
+    /// # Errors
+    /// * Fails if distance is not finite, e.g. a coordinate is NaN or infinite
+    pub fn try_distance(&self, second: &Self) -> Result<f64, String> {
+        let distance = self.distance(second);
+        if distance.is_finite() {
+            Ok(distance)
+        } else {
+            Err(format!("Distance between {:?} and {:?} is not finite", self, second))
+        }
+    }
+}
+
+/// Adds coordinates, treating points as vectors
+impl Add for Point2D {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {x: self.x + rhs.x, y: self.y + rhs.y}
+    }
+}
+
+/// Subtracts coordinates, giving the vector from rhs to self
+impl Sub for Point2D {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {x: self.x - rhs.x, y: self.y - rhs.y}
+    }
+}
+
+/// Scales both coordinates like scale
+impl Mul<f64> for Point2D {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        self.scale(rhs)
+    }
+}
+
+/// Represents locations with a 2D Point on a map that wraps around at its edges, like a globe
+/// 
+/// Moving past the right edge leads to the left edge, and moving past the bottom edge leads to the top edge
+/// 
+/// Deserialized through new, so invalid map sizes are rejected
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "ToroidalPoint2DData")]
+pub struct ToroidalPoint2D {
+    pub x: f64,
+    pub y: f64,
+    width: f64,
+    height: f64
+}
+
+/// Fields needed to create a ToroidalPoint2D
+#[derive(Deserialize)]
+struct ToroidalPoint2DData {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64
+}
+
+impl TryFrom<ToroidalPoint2DData> for ToroidalPoint2D {
+    type Error = String;
+
+    fn try_from(data: ToroidalPoint2DData) -> Result<Self, String> {
+        Self::new(data.x, data.y, data.width, data.height)
+    }
+}
+
+impl ToroidalPoint2D {
+    /// # Errors
+    /// * Fails if width or height is not positive and finite
+    pub fn new (x: f64, y: f64, width: f64, height: f64) -> Result<Self, String> {
+        if !(width.is_finite() && width > 0.0_f64 && height.is_finite() && height > 0.0_f64) {
+            return Err(format!("Map width and height must be positive and finite, not {width} and {height}"));
+        }
+        Ok(Self {x, y, width, height})
+    }
+
+    /// Width of the map, after which points wrap around to the left edge
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Height of the map, after which points wrap around to the top edge
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// Calculates shortest distance to second point, including paths that wrap around the edges of the map
+    /// 
+    /// Assumes both points lie on a map of the same size
+    pub fn distance(&self, second: &Self) -> f64 {
+        let wrapped_difference = |difference: f64, size: f64| {
+            let difference = difference.abs() % size;
+            difference.min(size - difference)
+        };
+        let x_difference = wrapped_difference(self.x - second.x, self.width);
+        let y_difference = wrapped_difference(self.y - second.y, self.height);
+        f64::sqrt(x_difference*x_difference + y_difference*y_difference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point2D, ToroidalPoint2D};
+
+    #[test]
+    fn vector_operators() {
+        let start = Point2D::new(1.0, 2.0);
+        let end = Point2D::new(4.0, -2.0);
+        assert_eq!(start + end, Point2D::new(5.0, 0.0));
+        assert_eq!(end - start, Point2D::new(3.0, -4.0));
+        assert_eq!(start - start, Point2D::default());
+        assert_eq!(end * 0.5, Point2D::new(2.0, -1.0));
+        assert_eq!(start.scale(-2.0), Point2D::new(-2.0, -4.0));
+        assert_eq!((end - start).magnitude(), 5.0);
+        assert_eq!((end - start).magnitude(), start.distance(&end));
+        assert_eq!(Point2D::default().magnitude(), 0.0);
+        // halfway point
+        assert_eq!(start + (end - start)*0.5, Point2D::new(2.5, 0.0));
+    }
+
+    #[test]
+    fn bearing() {
+        let origin = Point2D::new(1.0, 1.0);
+        assert_eq!(origin.bearing(&Point2D::new(5.0, 1.0)), 0.0);
+        assert_eq!(origin.bearing(&Point2D::new(1.0, 5.0)), std::f64::consts::FRAC_PI_2);
+        assert_eq!(origin.bearing(&Point2D::new(-3.0, 1.0)), std::f64::consts::PI);
+        assert_eq!(origin.bearing(&Point2D::new(1.0, -3.0)), -std::f64::consts::FRAC_PI_2);
+        assert_eq!(origin.bearing(&Point2D::new(2.0, 2.0)), std::f64::consts::FRAC_PI_4);
+    }
+
+    #[test]
+    fn toroidal_distance() {
+        // points near opposite edges are close
+        let west = ToroidalPoint2D::new(1.0, 50.0, 100.0, 100.0).unwrap();
+        let east = ToroidalPoint2D::new(99.0, 50.0, 100.0, 100.0).unwrap();
+        assert_eq!(west.distance(&east), 2.0);
+        assert_eq!(east.distance(&west), 2.0);
+        assert_eq!(Point2D::new(1.0, 50.0).distance(&Point2D::new(99.0, 50.0)), 98.0);
+
+        let north = ToroidalPoint2D::new(50.0, 2.0, 100.0, 100.0).unwrap();
+        let south = ToroidalPoint2D::new(50.0, 99.0, 100.0, 100.0).unwrap();
+        assert_eq!(north.distance(&south), 3.0);
+
+        let top_left = ToroidalPoint2D::new(1.0, 1.0, 100.0, 100.0).unwrap();
+        let bottom_right = ToroidalPoint2D::new(98.0, 97.0, 100.0, 100.0).unwrap();
+        assert_eq!(top_left.distance(&bottom_right), 5.0);
+
+        // points near the middle behave like a flat map
+        let first = ToroidalPoint2D::new(40.0, 40.0, 100.0, 100.0).unwrap();
+        let second = ToroidalPoint2D::new(43.0, 44.0, 100.0, 100.0).unwrap();
+        assert_eq!(first.distance(&second), 5.0);
+    }
+
+    #[test]
+    fn toroidal_map_size() {
+        assert!(ToroidalPoint2D::new(1.0, 1.0, 0.0, 100.0).is_err());
+        assert!(ToroidalPoint2D::new(1.0, 1.0, 100.0, -5.0).is_err());
+        assert!(ToroidalPoint2D::new(1.0, 1.0, f64::INFINITY, 100.0).is_err());
+        assert!(ToroidalPoint2D::new(1.0, 1.0, 100.0, f64::NAN).is_err());
+
+        let point = ToroidalPoint2D::new(1.0, 2.0, 100.0, 50.0).unwrap();
+        assert_eq!((point.width(), point.height()), (100.0, 50.0));
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(serde_json::from_str::<ToroidalPoint2D>(&json).unwrap(), point);
+        assert!(serde_json::from_str::<ToroidalPoint2D>(r#"{"x": 1.0, "y": 2.0, "width": 0.0, "height": 50.0}"#).is_err());
+    }
+}
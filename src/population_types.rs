@@ -1,7 +1,9 @@
-use self::{populated_area::PopulatedArea, population::Population};
+use self::{populated_area::PopulatedArea, population::Population, temporary_immunity_population::TemporaryImmunityPopulation};
 
+pub mod fractional_population;
 pub mod populated_area;
 pub mod population;
+pub mod temporary_immunity_population;
 
 /// Represents a type's ability to represent a population of people
 /// 
@@ -10,6 +12,11 @@ pub trait PopulationType : private::Sealed {
     fn population(&self) -> Population;
 
     fn set_population(&mut self, population: Population);
+
+    /// Called once per simulation update, after disease and travel, so populations can track time
+    /// 
+    /// Does nothing by default
+    fn advance_tick(&mut self) {}
 }
 
 impl PopulationType for Population {
@@ -37,6 +44,21 @@ impl PopulationType for PopulatedArea {
     
 }
 
+impl PopulationType for TemporaryImmunityPopulation {
+    fn population(&self) -> Population {
+        self.get_population()
+    }
+
+    fn set_population(&mut self, population: Population) {
+        self.set_population(population);
+    }
+
+    /// Makes recovered people whose immunity ran out healthy again
+    fn advance_tick(&mut self) {
+        self.advance_tick();
+    }
+}
+
 /// Represents a type's ability to have an associated population density
 /// 
 /// This trait may not be implemented by clients on their types
@@ -59,11 +81,12 @@ impl Density for PopulatedArea {
 }
 
 mod private {
-    use super::{populated_area::PopulatedArea, population::Population};
+    use super::{populated_area::PopulatedArea, population::Population, temporary_immunity_population::TemporaryImmunityPopulation};
 
     pub trait Sealed {}
 
     // Should cover all Population types specified in population_types module
     impl Sealed for PopulatedArea {}
     impl Sealed for Population {}
+    impl Sealed for TemporaryImmunityPopulation {}
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math_utils::get_random;
+
+use super::population::Population;
+
+/// Represents a human population as expected amounts of people, which may be fractional
+///
+/// Useful for repeatedly applying rates without losing fractions of people to rounding, only rounding when observing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FractionalPopulation {
+    pub healthy: f64,
+    pub infected: f64,
+    pub dead: f64,
+    pub recovered: f64
+}
+
+impl FractionalPopulation {
+    /// Rounds each group of people down or up randomly, with probabilities based on distance from each integer
+    ///
+    /// On average, each rounded group equals the fractional group
+    ///
+    /// Negative or NaN groups become 0, and groups too large for u32 saturate at u32::MAX
+    pub fn round_to_population(&self) -> Population {
+        Population {
+            healthy: Self::round_group(self.healthy),
+            infected: Self::round_group(self.infected),
+            dead: Self::round_group(self.dead),
+            recovered: Self::round_group(self.recovered)
+        }
+    }
+
+    // Same rounding as math_utils::probabilistic_round, but keeps f64 precision for large groups
+    fn round_group(group: f64) -> u32 {
+        if group.is_nan() || group <= 0.0_f64 {
+            return 0;
+        }
+        let rounded_down = group.floor();
+        let rounded = if get_random() < group - rounded_down { rounded_down + 1.0_f64 } else { rounded_down };
+        // casting saturates at u32::MAX
+        rounded as u32
+    }
+
+    /// Total expected people in population
+    pub fn get_total(&self) -> f64 {
+        self.healthy + self.infected + self.dead + self.recovered
+    }
+}
+
+impl From<Population> for FractionalPopulation {
+    fn from(population: Population) -> Self {
+        Self {
+            healthy: population.healthy.into(),
+            infected: population.infected.into(),
+            dead: population.dead.into(),
+            recovered: population.recovered.into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::population_types::population::Population;
+
+    use super::FractionalPopulation;
+
+    #[test]
+    fn round_to_population() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert_eq!(FractionalPopulation::from(population).round_to_population(), population);
+
+        let fractional = FractionalPopulation {healthy: 10.25, infected: -3.0, dead: f64::NAN, recovered: 1e12};
+        for _ in 0..50 {
+            let rounded = fractional.round_to_population();
+            assert!(rounded.healthy == 10 || rounded.healthy == 11);
+            assert_eq!(rounded.infected, 0);
+            assert_eq!(rounded.dead, 0);
+            assert_eq!(rounded.recovered, u32::MAX);
+        }
+    }
+
+    #[test]
+    fn fractional_growth_keeps_expected_total() {
+        // infections grow by 5% each tick, which integer-only populations lose to rounding
+        let growth_rate = 1.05;
+        let ticks = 20;
+        let expected_infected = 10.0*f64::powi(growth_rate, ticks);
+
+        let mut integer_population = Population {healthy: 1000, infected: 10, dead: 0, recovered: 0};
+        let mut fractional_population = FractionalPopulation::from(integer_population);
+        for _ in 0..ticks {
+            integer_population.infected = (integer_population.infected as f64*growth_rate) as u32;
+            fractional_population.infected *= growth_rate;
+        }
+
+        let rounded = fractional_population.round_to_population();
+        let fractional_error = (rounded.infected as f64 - expected_infected).abs();
+        let integer_error = (integer_population.infected as f64 - expected_infected).abs();
+        assert!(fractional_error <= 1.0);
+        assert!(fractional_error < integer_error);
+        assert_eq!(rounded.healthy, 1000);
+    }
+}
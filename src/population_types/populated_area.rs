@@ -1,10 +1,16 @@
+use serde::{Deserialize, Serialize};
+
 use super::population::Population;
 
 
 
 /// Represents a human population with an associated area and population density
-/// 
+///
 /// Not to be confused with Region
+/// 
+/// Deserialized through new_from_area, so invalid areas are rejected and densities are recalculated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "PopulatedAreaData")]
 pub struct PopulatedArea {
     population: Population,
     area: f32,
@@ -12,49 +18,142 @@ pub struct PopulatedArea {
     total_density: f32
 }
 
+/// Fields needed to create a PopulatedArea, any serialized densities are ignored
+#[derive(Deserialize)]
+struct PopulatedAreaData {
+    population: Population,
+    area: f32
+}
+
+impl TryFrom<PopulatedAreaData> for PopulatedArea {
+    type Error = String;
+
+    fn try_from(data: PopulatedAreaData) -> Result<Self, String> {
+        Self::new_from_area(data.area, data.population)
+    }
+}
+
 /// Invariants:
 /// * total density * area = population total
 /// * alive density * area = non dead total
+/// * area is positive and finite
 impl PopulatedArea {
     /// Creates based on a given area and population
-    pub fn new_from_area(area: f32, population: Population) {
-        todo!()
+    /// # Errors
+    /// * Fails if area is not positive and finite
+    pub fn new_from_area(area: f32, population: Population) -> Result<Self, String> {
+        if !Self::valid_area(area) {
+            return Err(format!("Area must be positive and finite, not {area}"));
+        }
+        let mut populated_area = Self {population, area, alive_density: 0.0, total_density: 0.0};
+        populated_area.update_densities();
+        Ok(populated_area)
     }
 
     /// Creates based on a given population density and population
-    pub fn new_from_density(density: f32, population: Population) {
-        todo!()
+    /// # Errors
+    /// * Fails if density is not positive and finite
+    /// * Fails if population is empty, since no area can be derived from it
+    pub fn new_from_density(density: f32, population: Population) -> Result<Self, String> {
+        if !(density.is_finite() && density > 0.0_f32) {
+            return Err(format!("Density must be positive and finite, not {density}"));
+        }
+        Self::new_from_area(population.get_total() as f32/density, population)
+    }
+
+    fn valid_area(area: f32) -> bool {
+        area.is_finite() && area > 0.0_f32
+    }
+
+    fn update_densities(&mut self) {
+        self.total_density = self.population.get_total() as f32/self.area;
+        self.alive_density = self.population.get_alive() as f32/self.area;
     }
 
     /// Get population
     pub fn get_population(&self) -> Population {
-        todo!()
+        self.population
     }
 
     /// Get area
     pub fn get_area(&self) -> f32 {
-        todo!()
+        self.area
     }
 
     /// Set population
     /// Returns new total density and new alive density
-    pub fn set_population(&mut self, population: Population) {
-        todo!()
+    pub fn set_population(&mut self, population: Population) -> (f32, f32) {
+        self.population = population;
+        self.update_densities();
+        (self.total_density, self.alive_density)
     }
 
     /// Set area
     /// Returns new total density and new alive density
-    pub fn set_area(&mut self, area: f32) {
-        todo!()
+    /// # Errors
+    /// * Fails if area is not positive and finite, leaving area unchanged
+    pub fn set_area(&mut self, area: f32) -> Result<(f32, f32), String> {
+        if !Self::valid_area(area) {
+            return Err(format!("Area must be positive and finite, not {area}"));
+        }
+        self.area = area;
+        self.update_densities();
+        Ok((self.total_density, self.alive_density))
     }
 
     /// Gets alive population density
     pub fn alive_density(&self) -> f32 {
-        todo!()
+        self.alive_density
     }
 
     /// Gets total population density
     pub fn total_density(&self) -> f32 {
-        todo!()
+        self.total_density
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::population_types::population::Population;
+
+    use super::PopulatedArea;
+
+    #[test]
+    fn populated_area_densities() {
+        let population = Population {healthy: 600, infected: 200, dead: 200, recovered: 0};
+        let mut area = PopulatedArea::new_from_area(10.0, population).unwrap();
+        assert_eq!(area.total_density(), 100.0);
+        assert_eq!(area.alive_density(), 80.0);
+
+        assert_eq!(area.set_population(Population {healthy: 200, infected: 200, dead: 600, recovered: 0}), (100.0, 40.0));
+        assert_eq!(area.set_area(20.0), Ok((50.0, 20.0)));
+        assert!(area.set_area(0.0).is_err());
+        assert!(area.set_area(f32::NAN).is_err());
+        assert_eq!(area.get_area(), 20.0);
+
+        let from_density = PopulatedArea::new_from_density(50.0, population).unwrap();
+        assert_eq!(from_density.get_area(), 20.0);
+        assert_eq!(from_density.get_population(), population);
+
+        assert!(PopulatedArea::new_from_area(-1.0, population).is_err());
+        assert!(PopulatedArea::new_from_density(0.0, population).is_err());
+        assert!(PopulatedArea::new_from_density(50.0, Population::default()).is_err());
+    }
+
+    #[test]
+    fn populated_area_deserialization() {
+        let population = Population {healthy: 600, infected: 200, dead: 200, recovered: 0};
+        let area = PopulatedArea::new_from_area(10.0, population).unwrap();
+        let json = serde_json::to_string(&area).unwrap();
+        assert_eq!(serde_json::from_str::<PopulatedArea>(&json).unwrap(), area);
+
+        // densities not matching population are recalculated
+        let mismatched = r#"{"population": {"healthy": 600, "infected": 200, "dead": 200, "recovered": 0}, "area": 10.0, "alive_density": 3.0, "total_density": 7.0}"#;
+        assert_eq!(serde_json::from_str::<PopulatedArea>(mismatched).unwrap(), area);
+
+        for invalid_area in ["-1.0", "0.0", "1e39"] {
+            let invalid = format!(r#"{{"population": {{"healthy": 600, "infected": 200, "dead": 200, "recovered": 0}}, "area": {invalid_area}}}"#);
+            assert!(serde_json::from_str::<PopulatedArea>(&invalid).is_err(), "{invalid_area}");
+        }
+    }
+}
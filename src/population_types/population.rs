@@ -1,11 +1,12 @@
-use std::ops::Add;
+use std::{cmp::Ordering, iter::Sum, ops::{Add, Mul}};
 
 use serde::{Deserialize, Serialize};
 
-use crate::math_utils::get_random;
+use crate::math_utils::{binomial_sample, probabilistic_round, random_u32_in};
 
 #[derive(Debug, Clone, Default, PartialEq, Copy, Serialize, Deserialize)]
 /** Represents any group of people */
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Population {
     pub healthy: u32,
     pub infected: u32,
@@ -26,6 +27,37 @@ impl Add for Population {
 
 }
 
+/// Scales population by a scalar factor, rounding to the nearest integer like scale
+impl Mul<f64> for Population {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        self.scale(rhs)
+    }
+}
+
+/// Scales population by a scalar factor, rounding to the nearest integer like scale
+impl Mul<f64> for &Population {
+    type Output = Population;
+
+    fn mul(self, rhs: f64) -> Population {
+        self.scale(rhs)
+    }
+}
+
+/// Sums populations, saturating at the maximum size of each group of people instead of overflowing
+impl Sum for Population {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Population::default(), |acc, pop| acc.saturating_add(pop))
+    }
+}
+
+impl<'a> Sum<&'a Population> for Population {
+    fn sum<I: Iterator<Item = &'a Population>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
 impl Population {
     /* Creates a population of healthy people */
     pub fn new_healthy (initial_pop: u32) -> Self {
@@ -35,11 +67,11 @@ impl Population {
     /* Create a population with a certain size, but random proportions of infected, healthy, etc. */
     pub fn new_random(size: u32) -> Self {
         let mut remaining_amount = size;
-        let healthy = (((remaining_amount + 1) as f64)*get_random()) as u32;
+        let healthy = random_u32_in(0, remaining_amount);
         remaining_amount -= healthy;
-        let dead = (((remaining_amount + 1) as f64)*get_random()) as u32;
+        let dead = random_u32_in(0, remaining_amount);
         remaining_amount -= dead;
-        let infected = (((remaining_amount + 1) as f64)*get_random()) as u32;
+        let infected = random_u32_in(0, remaining_amount);
         remaining_amount -= infected;
         let recovered = remaining_amount;
         debug_assert!(healthy + dead + recovered + infected == size, "{}", 
@@ -73,6 +105,139 @@ impl Population {
         Self { healthy: new_healthy, infected: new_infected, dead: new_dead, recovered: new_recovered }
     }
 
+    /// Creates a new population by scaling this population by a scalar factor
+    /// 
+    /// Note: Scaling will always round to the nearest integer, like scale
+    /// # Errors
+    /// * Fails if scalar is negative, NaN or infinite
+    /// * Fails if any scaled group of people cannot be represented by a u32
+    pub fn checked_scale(&self, scalar: f64) -> Result<Population, String> {
+        if !scalar.is_finite() || scalar < 0.0_f64 {
+            return Err(format!("Cannot scale population by {}, scalar must be finite and non-negative", scalar));
+        }
+        let scale_group = |group: u32| {
+            let scaled = (scalar*(group as f64)).round();
+            if scaled > u32::MAX as f64 {
+                Err(format!("Scaling {} people by {} does not fit in a population", group, scalar))
+            } else {
+                Ok(scaled as u32)
+            }
+        };
+        Ok(Self { healthy: scale_group(self.healthy)?, infected: scale_group(self.infected)?, dead: scale_group(self.dead)?, recovered: scale_group(self.recovered)? })
+    }
+
+    /// Creates a new population by scaling this population by a scalar factor
+    /// 
+    /// Note: Each scaled group of people is rounded up with a chance equal to its fractional part, so the expected result is exact
+    /// # Errors
+    /// * Fails if scalar is negative, NaN or infinite
+    /// * Fails if any scaled group of people cannot be represented by a u32
+    pub fn scale_probabilistic(&self, scalar: f64) -> Result<Population, String> {
+        if !scalar.is_finite() || scalar < 0.0_f64 {
+            return Err(format!("Cannot scale population by {}, scalar must be finite and non-negative", scalar));
+        }
+        let scale_group = |group: u32| probabilistic_round((scalar*(group as f64)) as f32);
+        Ok(Self { healthy: scale_group(self.healthy)?, infected: scale_group(self.infected)?, dead: scale_group(self.dead)?, recovered: scale_group(self.recovered)? })
+    }
+
+    /// Adds two populations together
+    /// 
+    /// Each group of people saturates at u32::MAX instead of overflowing
+    pub fn saturating_add(&self, other: Population) -> Population {
+        Population {
+            healthy: self.healthy.saturating_add(other.healthy),
+            infected: self.infected.saturating_add(other.infected),
+            dead: self.dead.saturating_add(other.dead),
+            recovered: self.recovered.saturating_add(other.recovered)
+        }
+    }
+
+    /// Creates population where every alive person has recovered, keeping the dead
+    /// 
+    /// Recovered people saturate at u32::MAX, so the total is only kept if it fits in a u32
+    pub fn recover_all(&self) -> Population {
+        let recovered = self.healthy.saturating_add(self.infected).saturating_add(self.recovered);
+        Population {healthy: 0, infected: 0, dead: self.dead, recovered}
+    }
+
+    /// Creates population where every person has died
+    /// 
+    /// Dead people saturate at u32::MAX, so the total is only kept if it fits in a u32
+    pub fn kill_all(&self) -> Population {
+        let dead = self.get_total_u64().try_into().unwrap_or(u32::MAX);
+        Population {healthy: 0, infected: 0, dead, recovered: 0}
+    }
+
+    /// Draws population as a bar exactly width characters long, each group of people taking space proportional to its size
+    /// 
+    /// Healthy people are drawn as █, infected as ▓, recovered as ▒ and dead as ░
+    /// 
+    /// An empty population is drawn as spaces
+    pub fn bar(&self, width: usize) -> String {
+        let total = u128::from(self.get_total_u64());
+        if total == 0 {
+            return " ".repeat(width);
+        }
+        let width_u128 = width as u128;
+        let groups = [(self.healthy, '█'), (self.infected, '▓'), (self.recovered, '▒'), (self.dead, '░')];
+        // each group gets its rounded down share, and leftover characters go to the groups that lost the most to rounding
+        let mut shares: Vec<(usize, u128)> = groups.iter().map(|(group, _)| {
+            let exact = u128::from(*group).saturating_mul(width_u128);
+            ((exact.checked_div(total).unwrap_or_default()) as usize, exact.checked_rem(total).unwrap_or_default())
+        }).collect();
+        let assigned: usize = shares.iter().map(|(share, _)| *share).sum();
+        let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+        by_remainder.sort_by_key(|index| std::cmp::Reverse(shares[*index].1));
+        for index in by_remainder.into_iter().take(width.saturating_sub(assigned)) {
+            shares[index].0 = shares[index].0.saturating_add(1);
+        }
+        groups.iter().zip(shares).map(|((_, symbol), (share, _))| symbol.to_string().repeat(share)).collect()
+    }
+
+    /// Checks whether anyone in population is infected and could still spread disease
+    pub fn has_active_infection(&self) -> bool {
+        self.infected > 0
+    }
+
+    /// Compares populations by their total amount of people, including dead
+    pub fn cmp_by_total(&self, other: &Population) -> Ordering {
+        self.get_total_u64().cmp(&other.get_total_u64())
+    }
+
+    /// Takes the larger amount of each group of people between two populations
+    pub fn compartment_max(&self, other: &Population) -> Population {
+        Population {
+            healthy: self.healthy.max(other.healthy),
+            infected: self.infected.max(other.infected),
+            dead: self.dead.max(other.dead),
+            recovered: self.recovered.max(other.recovered)
+        }
+    }
+
+    /// Takes the smaller amount of each group of people between two populations
+    pub fn compartment_min(&self, other: &Population) -> Population {
+        Population {
+            healthy: self.healthy.min(other.healthy),
+            infected: self.infected.min(other.infected),
+            dead: self.dead.min(other.dead),
+            recovered: self.recovered.min(other.recovered)
+        }
+    }
+
+    /// Linearly interpolates between this population and another population
+    /// 
+    /// t = 0.0 gives this population, and t = 1.0 gives the other population
+    /// 
+    /// Note: Each group of people is rounded to the nearest integer
+    pub fn lerp(&self, other: &Population, t: f64) -> Population {
+        let lerp_group = |start: u32, end: u32| (start as f64 + t*(end as f64 - start as f64)).round() as u32;
+        Population {
+            healthy: lerp_group(self.healthy, other.healthy),
+            infected: lerp_group(self.infected, other.infected),
+            dead: lerp_group(self.dead, other.dead),
+            recovered: lerp_group(self.recovered, other.recovered)
+        }
+    }
 
     /* Returns all non-dead people in population */
     pub fn get_alive(&self) -> u32 {
@@ -84,6 +249,44 @@ impl Population {
         self.dead + self.healthy + self.recovered + self.infected
     }
 
+    /// Returns total population, including dead, without risk of overflowing
+    pub fn get_total_u64(&self) -> u64 {
+        u64::from(self.dead) + u64::from(self.healthy) + u64::from(self.recovered) + u64::from(self.infected)
+    }
+
+    /// Randomly picks a group of exactly amount people from this population
+    /// 
+    /// Each group of people is drawn one after another, approximating a multivariate hypergeometric draw with binomial draws
+    /// 
+    /// Picked group can always be removed from this population using emigrate
+    /// # Errors
+    /// * Fails if amount is greater than total population
+    pub fn partition_random(&self, amount: u32) -> Result<Population, String> {
+        let mut remaining_total = self.get_total_u64();
+        if u64::from(amount) > remaining_total {
+            return Err(format!("Cannot pick {} people from a population of {} people", amount, remaining_total));
+        }
+        let mut remaining_amount = amount;
+        let mut pick_group = |group: u32| {
+            if remaining_total == 0 {
+                return 0;
+            }
+            // must leave enough people in later groups to pick the rest from
+            let later_groups_total = remaining_total.saturating_sub(u64::from(group));
+            let min_picked = u64::from(remaining_amount).saturating_sub(later_groups_total) as u32;
+            let max_picked = group.min(remaining_amount);
+            let picked = binomial_sample(remaining_amount, group as f64/remaining_total as f64).clamp(min_picked, max_picked);
+            remaining_total = later_groups_total;
+            remaining_amount = remaining_amount.saturating_sub(picked);
+            picked
+        };
+        let healthy = pick_group(self.healthy);
+        let infected = pick_group(self.infected);
+        let dead = pick_group(self.dead);
+        let recovered = pick_group(self.recovered);
+        Ok(Population {healthy, infected, dead, recovered})
+    }
+
     // Calculates population resulting from removing a group from this population
     // Errors if group cannot be extracted from this population
     pub fn emigrate(&self, group: Self) -> Result<Population, String> {
@@ -103,11 +306,61 @@ impl Population {
             Ok(Population { healthy: new_healthy, infected: new_infected, dead: new_dead, recovered: new_recovered })
         }
     }
+
+    // Removes as much of a group from this population as possible
+    // Returns people actually removed and population remaining
+    pub fn emigrate_saturating(&self, group: Self) -> (Population, Population) {
+        let removed = self.compartment_min(&group);
+        let remaining = Population {
+            healthy: self.healthy.saturating_sub(removed.healthy),
+            infected: self.infected.saturating_sub(removed.infected),
+            dead: self.dead.saturating_sub(removed.dead),
+            recovered: self.recovered.saturating_sub(removed.recovered)
+        };
+        (removed, remaining)
+    }
+}
+
+/// Finds population with the most people in total, including dead
+/// 
+/// Returns the first one found if several share the largest total, or None if there are no populations
+pub fn most_populous<'a, I>(populations: I) -> Option<&'a Population> where I: IntoIterator<Item = &'a Population> {
+    populations.into_iter().reduce(|most, population| if population.cmp_by_total(most) == Ordering::Greater { population } else { most })
+}
+
+/// Serializes populations with one letter keys (h, i, d, r), for compact JSON of large histories
+/// 
+/// Use on fields with `#[serde(with = "functionality::population_types::population::compact")]`
+pub mod compact {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Population;
+
+    #[derive(Serialize, Deserialize)]
+    struct CompactPopulation {
+        h: u32,
+        i: u32,
+        d: u32,
+        r: u32
+    }
+
+    pub fn serialize<S>(population: &Population, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        CompactPopulation {h: population.healthy, i: population.infected, d: population.dead, r: population.recovered}.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Population, D::Error> where D: Deserializer<'de> {
+        let compact = CompactPopulation::deserialize(deserializer)?;
+        Ok(Population {healthy: compact.h, infected: compact.i, dead: compact.d, recovered: compact.r})
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Population;
+    use crate::math_utils::random_u32_in;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{most_populous, Population};
 
     #[test]
     fn scale_truncate() {
@@ -133,6 +386,114 @@ mod tests {
         assert_eq!(trisected_population, expected_population);
     }
 
+    #[test]
+    fn checked_scale() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert_eq!(population.checked_scale(0.5), Ok(population.scale(0.5)));
+        assert_eq!(population.checked_scale(0.0), Ok(Population::default()));
+
+        // invalid scalars
+        assert!(population.checked_scale(-0.5).is_err());
+        assert!(population.checked_scale(f64::NAN).is_err());
+        assert!(population.checked_scale(f64::INFINITY).is_err());
+
+        // overflowing scalars
+        assert!(population.checked_scale(u32::MAX as f64).is_err());
+        let big_population = Population::new_healthy(u32::MAX);
+        assert!(big_population.checked_scale(1.5).is_err());
+        assert_eq!(big_population.checked_scale(1.0), Ok(big_population));
+    }
+
+    #[test]
+    fn scale_probabilistic() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert_eq!(population.scale_probabilistic(2.0), Ok(Population {healthy: 300, infected: 150, dead: 222, recovered: 4}));
+        assert_eq!(population.scale_probabilistic(0.0), Ok(Population::default()));
+        assert!(population.scale_probabilistic(-0.5).is_err());
+        assert!(population.scale_probabilistic(f64::NAN).is_err());
+        assert!(Population::new_healthy(u32::MAX).scale_probabilistic(1.5).is_err());
+
+        // scaled groups are rounded to either neighbouring integer, and are right on average
+        let mut total_infected = 0;
+        for _ in 0..1000 {
+            let scaled = population.scale_probabilistic(0.5).unwrap();
+            assert!((37..=38).contains(&scaled.infected));
+            assert_eq!(scaled.healthy, 75);
+            total_infected += scaled.infected;
+        }
+        assert!((37300..=37700).contains(&total_infected));
+    }
+
+    #[test]
+    fn mul() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert_eq!(population * 0.5, population.scale(0.5));
+        assert_eq!(&population * 0.5, population.scale(0.5));
+        assert_eq!(population * 0.333333, population.scale(0.333333));
+    }
+
+    #[test]
+    fn lerp() {
+        let start = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        let end = Population {healthy: 50, infected: 175, dead: 111, recovered: 0};
+        assert_eq!(start.lerp(&end, 0.0), start);
+        assert_eq!(start.lerp(&end, 1.0), end);
+        assert_eq!(start.lerp(&end, 0.5), Population {healthy: 100, infected: 125, dead: 111, recovered: 1});
+
+        let huge = Population::new_healthy(u32::MAX);
+        assert_eq!(huge.lerp(&start, 0.0), huge);
+        assert_eq!(huge.lerp(&start, 1.0), start);
+    }
+
+    #[test]
+    fn sum() {
+        let populations = vec![
+            Population {healthy: 150, infected: 75, dead: 111, recovered: 2},
+            Population {healthy: 10, infected: 0, dead: 1, recovered: 20},
+            Population {healthy: 0, infected: 5, dead: 0, recovered: 0},
+        ];
+        let expected_population = Population {healthy: 160, infected: 80, dead: 112, recovered: 22};
+        assert_eq!(populations.iter().sum::<Population>(), expected_population);
+        assert_eq!(populations.into_iter().sum::<Population>(), expected_population);
+        assert_eq!(Vec::<Population>::new().into_iter().sum::<Population>(), Population::default());
+
+        // saturates instead of overflowing
+        let huge_populations = [Population::new_healthy(u32::MAX), Population::new_healthy(1)];
+        assert_eq!(huge_populations.iter().sum::<Population>(), Population::new_healthy(u32::MAX));
+    }
+
+    #[test]
+    fn get_total_u64() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert_eq!(population.get_total_u64(), u64::from(population.get_total()));
+
+        let huge_populations = [
+            Population {healthy: u32::MAX, infected: u32::MAX, dead: u32::MAX, recovered: u32::MAX},
+            Population::new_healthy(u32::MAX - 1),
+            Population {healthy: 0, infected: u32::MAX, dead: 0, recovered: 0},
+        ];
+        let total: u64 = huge_populations.iter().map(|pop| pop.get_total_u64()).sum();
+        assert_eq!(total, 6*u64::from(u32::MAX) - 1);
+    }
+
+    #[test]
+    fn partition_random() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        assert!(population.partition_random(339).is_err());
+        assert_eq!(population.partition_random(338), Ok(population));
+        assert_eq!(population.partition_random(0), Ok(Population::default()));
+
+        for size in [0, 1, 3, 50, 100, 700, 15000, 8300000, 4_000_000_000] {
+            for _ in 0..30 {
+                let random_pop = Population::new_random(size);
+                let amount = random_u32_in(0, size);
+                let group = random_pop.partition_random(amount).unwrap();
+                assert_eq!(group.get_total(), amount);
+                assert!(random_pop.emigrate(group).is_ok());
+            }
+        }
+    }
+
     #[test]
     fn new_random() {
         let initial_sizes: [u32; 9] = [0, 1, 3, 50, 100, 700, 15000, 8300000, 4_000_000_000];
@@ -144,4 +505,115 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn compartment_max_min() {
+        let first = Population {healthy: 150, infected: 75, dead: 0, recovered: 2};
+        let second = Population {healthy: 100, infected: 80, dead: 3, recovered: 2};
+        assert_eq!(first.compartment_max(&second), Population {healthy: 150, infected: 80, dead: 3, recovered: 2});
+        assert_eq!(first.compartment_min(&second), Population {healthy: 100, infected: 75, dead: 0, recovered: 2});
+        assert_eq!(first.compartment_max(&second), second.compartment_max(&first));
+        assert_eq!(first.compartment_min(&first), first);
+    }
+
+    #[test]
+    fn emigrate_saturating() {
+        let population = Population {healthy: 150, infected: 75, dead: 0, recovered: 2};
+        let group = Population {healthy: 100, infected: 80, dead: 3, recovered: 2};
+        assert!(population.emigrate(group).is_err());
+        let (removed, remaining) = population.emigrate_saturating(group);
+        assert_eq!(removed, Population {healthy: 100, infected: 75, dead: 0, recovered: 2});
+        assert_eq!(remaining, Population {healthy: 50, infected: 0, dead: 0, recovered: 0});
+        assert_eq!(removed + remaining, population);
+
+        // behaves like emigrate when group fits
+        let small_group = Population {healthy: 10, infected: 5, dead: 0, recovered: 1};
+        assert_eq!(population.emigrate_saturating(small_group), (small_group, population.emigrate(small_group).unwrap()));
+    }
+
+    #[test]
+    fn sort_by_total() {
+        let mut populations = vec![
+            Population {healthy: 50, infected: 0, dead: 100, recovered: 0},
+            Population::new_healthy(20),
+            Population {healthy: 0, infected: u32::MAX, dead: u32::MAX, recovered: 0},
+            Population {healthy: 10, infected: 5, dead: 0, recovered: 5},
+            Population::new_healthy(150),
+        ];
+        assert_eq!(most_populous(&populations), Some(&populations[2]));
+        // ties keep the first population
+        assert_eq!(most_populous([&populations[1], &populations[3]]), Some(&populations[1]));
+        assert_eq!(most_populous([&populations[3], &populations[1]]), Some(&populations[3]));
+        assert_eq!(most_populous(&[]), None);
+
+        populations.sort_by(Population::cmp_by_total);
+        let totals: Vec<u64> = populations.iter().map(Population::get_total_u64).collect();
+        assert_eq!(totals, vec![20, 20, 150, 150, u64::from(u32::MAX) * 2]);
+        assert_eq!(populations[0], Population::new_healthy(20));
+        assert_eq!(populations[2], Population {healthy: 50, infected: 0, dead: 100, recovered: 0});
+    }
+
+    #[test]
+    fn recover_and_kill_all() {
+        let population = Population {healthy: 150, infected: 75, dead: 111, recovered: 2};
+        let recovered = population.recover_all();
+        assert_eq!(recovered, Population {healthy: 0, infected: 0, dead: 111, recovered: 227});
+        assert_eq!(recovered.get_total(), population.get_total());
+        let dead = population.kill_all();
+        assert_eq!(dead, Population {healthy: 0, infected: 0, dead: 338, recovered: 0});
+        assert_eq!(dead.get_total(), population.get_total());
+
+        assert_eq!(Population::default().recover_all(), Population::default());
+        assert_eq!(Population::default().kill_all(), Population::default());
+        let huge = Population {healthy: u32::MAX, infected: 1, dead: 0, recovered: 0};
+        assert_eq!(huge.recover_all().recovered, u32::MAX);
+        assert_eq!(huge.kill_all().dead, u32::MAX);
+    }
+
+    #[test]
+    fn compact_serialization() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Snapshot {
+            #[serde(with = "super::compact")]
+            population: Population
+        }
+
+        let snapshot = Snapshot {population: Population {healthy: 150, infected: 75, dead: 111, recovered: 2}};
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(json, r#"{"population":{"h":150,"i":75,"d":111,"r":2}}"#);
+        assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+        assert!(json.len() < serde_json::to_string(&snapshot.population).unwrap().len());
+        assert!(serde_json::from_str::<Snapshot>(r#"{"population":{"healthy":150,"infected":75,"dead":111,"recovered":2}}"#).is_err());
+    }
+
+    #[test]
+    fn bar() {
+        let population = Population {healthy: 50, infected: 25, dead: 0, recovered: 25};
+        assert_eq!(population.bar(8), "████▓▓▒▒");
+        assert_eq!(population.bar(0), "");
+
+        // leftover characters go to the groups closest to rounding up
+        let population = Population {healthy: 1, infected: 1, dead: 1, recovered: 0};
+        assert_eq!(population.bar(4), "██▓░");
+        assert_eq!(Population::default().bar(5), "     ");
+
+        for _ in 0..100 {
+            let population = Population::new_random(random_u32_in(1, u32::MAX));
+            for width in [1, 7, 40, 101] {
+                let bar = population.bar(width);
+                assert_eq!(bar.chars().count(), width);
+                let healthy_chars = bar.chars().filter(|symbol| *symbol == '█').count() as f64;
+                let expected = population.healthy as f64 * width as f64 / population.get_total_u64() as f64;
+                assert!((healthy_chars - expected).abs() < 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn has_active_infection() {
+        assert!(Population {healthy: 100, infected: 1, dead: 0, recovered: 0}.has_active_infection());
+        assert!(!Population {healthy: 0, infected: 0, dead: 40, recovered: 60}.has_active_infection());
+        assert!(!Population::new_healthy(100).has_active_infection());
+        assert!(!Population::default().has_active_infection());
+    }
 }
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::population::Population;
+
+/// Represents a human population whose recovered people only stay immune for a fixed number of ticks
+/// 
+/// Recovered people are grouped into cohorts by the tick they recovered on, and become healthy again once their immunity runs out
+/// 
+/// Ticks are counted by advance_tick, which simulations call once per update through PopulationType
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemporaryImmunityPopulation {
+    population: Population,
+    immunity_duration: u32,
+    // number of times advance_tick has been called
+    tick: u32,
+    // tick of recovery and amount of recovered people, oldest cohort first, only holding cohorts with people in them
+    recovered_cohorts: VecDeque<(u32, u32)>
+}
+
+/// Invariants:
+/// * Sum of recovered cohorts = recovered population
+/// * Every cohort recovered less than immunity duration ticks ago
+impl TemporaryImmunityPopulation {
+    /// Creates population whose recovered people stay immune for immunity_duration ticks
+    /// 
+    /// Initially recovered people are treated as having just recovered
+    /// # Errors
+    /// * Fails if immunity duration is 0
+    pub fn new(population: Population, immunity_duration: u32) -> Result<Self, String> {
+        if immunity_duration == 0 {
+            return Err("Immunity duration must be at least 1 tick".to_owned());
+        }
+        let mut recovered_cohorts = VecDeque::new();
+        if population.recovered > 0 {
+            recovered_cohorts.push_back((0, population.recovered));
+        }
+        Ok(Self {population, immunity_duration, tick: 0, recovered_cohorts})
+    }
+
+    /// Get population
+    pub fn get_population(&self) -> Population {
+        self.population
+    }
+
+    /// Get number of ticks recovered people stay immune for
+    pub fn immunity_duration(&self) -> u32 {
+        self.immunity_duration
+    }
+
+    /// Set population
+    /// 
+    /// Newly recovered people are treated as having recovered this tick
+    /// 
+    /// If recovered people are removed, those closest to losing immunity are removed first
+    pub fn set_population(&mut self, population: Population) {
+        let tracked_recovered: u32 = self.recovered_cohorts.iter().fold(0_u32, |sum, (_, count)| sum.saturating_add(*count));
+        if population.recovered >= tracked_recovered {
+            let newly_recovered = population.recovered.saturating_sub(tracked_recovered);
+            match self.recovered_cohorts.back_mut() {
+                Some((tick, count)) if *tick == self.tick => *count = count.saturating_add(newly_recovered),
+                _ if newly_recovered > 0 => self.recovered_cohorts.push_back((self.tick, newly_recovered)),
+                _ => {}
+            }
+        } else {
+            let mut to_remove = tracked_recovered.saturating_sub(population.recovered);
+            while to_remove > 0 {
+                let Some((_, count)) = self.recovered_cohorts.front_mut() else {
+                    break;
+                };
+                let removed = to_remove.min(*count);
+                *count = count.saturating_sub(removed);
+                to_remove = to_remove.saturating_sub(removed);
+                if *count == 0 {
+                    self.recovered_cohorts.pop_front();
+                }
+            }
+        }
+        self.population = population;
+    }
+
+    /// Moves on to the next tick, making recovered people whose immunity ran out healthy again
+    /// 
+    /// Returns new population
+    pub fn advance_tick(&mut self) -> Population {
+        self.tick = self.tick.saturating_add(1);
+        while let Some((tick, count)) = self.recovered_cohorts.front().copied() {
+            if tick.saturating_add(self.immunity_duration) > self.tick {
+                break;
+            }
+            self.recovered_cohorts.pop_front();
+            self.population.recovered = self.population.recovered.saturating_sub(count);
+            self.population.healthy = self.population.healthy.saturating_add(count);
+        }
+        self.population
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::population_types::{population::Population, PopulationType};
+
+    use super::TemporaryImmunityPopulation;
+
+    #[test]
+    fn immunity_expires() {
+        assert!(TemporaryImmunityPopulation::new(Population::new_healthy(10), 0).is_err());
+
+        let mut population = TemporaryImmunityPopulation::new(Population {healthy: 100, infected: 0, dead: 0, recovered: 0}, 3).unwrap();
+        // 20 people recover on tick 0
+        population.set_population(Population {healthy: 80, infected: 0, dead: 0, recovered: 20});
+        population.advance_tick();
+        // 5 more people recover on tick 1
+        population.set_population(Population {healthy: 75, infected: 0, dead: 0, recovered: 25});
+        population.advance_tick();
+        assert_eq!(population.population(), Population {healthy: 75, infected: 0, dead: 0, recovered: 25});
+
+        // first cohort loses immunity 3 ticks after recovering
+        population.advance_tick();
+        assert_eq!(population.population(), Population {healthy: 95, infected: 0, dead: 0, recovered: 5});
+
+        // second cohort loses immunity 3 ticks after recovering
+        population.advance_tick();
+        assert_eq!(population.population(), Population {healthy: 100, infected: 0, dead: 0, recovered: 0});
+    }
+
+    #[test]
+    fn long_immunity() {
+        // cohorts are only stored when people recover, so huge durations need no memory up front
+        let mut population = TemporaryImmunityPopulation::new(Population {healthy: 100, infected: 0, dead: 0, recovered: 10}, u32::MAX).unwrap();
+        assert_eq!(population.immunity_duration(), u32::MAX);
+        for _ in 0..100 {
+            population.advance_tick();
+        }
+        assert_eq!(population.population(), Population {healthy: 100, infected: 0, dead: 0, recovered: 10});
+    }
+
+    #[test]
+    fn removing_recovered() {
+        let mut population = TemporaryImmunityPopulation::new(Population {healthy: 100, infected: 0, dead: 0, recovered: 10}, 2).unwrap();
+        population.advance_tick();
+        population.set_population(Population {healthy: 100, infected: 0, dead: 0, recovered: 30});
+        // 15 recovered people leave, taking the 10 that recovered first
+        population.set_population(Population {healthy: 100, infected: 0, dead: 0, recovered: 15});
+        population.advance_tick();
+        assert_eq!(population.population(), Population {healthy: 100, infected: 0, dead: 0, recovered: 15});
+        population.advance_tick();
+        assert_eq!(population.population(), Population {healthy: 115, infected: 0, dead: 0, recovered: 0});
+    }
+}
@@ -0,0 +1,133 @@
+// Responsible for quickly finding ports near a location
+
+use crate::{point::Point2D, region::PortID, transportation_graph::PortGraph};
+
+/** Node of a 2D k-d tree, splitting space along x at even depths and along y at odd depths */
+struct KdNode {
+    pos: Point2D,
+    id: PortID,
+    left: Option<Box<KdNode>>,
+    right: Option<Box<KdNode>>
+}
+
+/// Finds ports of a graph closest to a point, faster than checking every port
+///
+/// Built from a snapshot of the graph's ports, so it must be rebuilt if ports are added or moved
+///
+/// Ports whose positions are not finite are left out
+pub struct PortSpatialIndex {
+    root: Option<Box<KdNode>>,
+    len: usize
+}
+
+impl PortSpatialIndex {
+    /// Indexes every port of graph with a finite position
+    pub fn new(graph: &PortGraph) -> Self {
+        let mut ports: Vec<(Point2D, PortID)> = graph.iter_ports()
+            .filter(|port| port.pos.is_finite())
+            .map(|port| (port.pos, port.id))
+            .collect();
+        // sort so that the tree does not depend on graph's iteration order
+        ports.sort_by_key(|(_, id)| *id);
+        let len = ports.len();
+        Self {root: Self::build(ports, 0), len}
+    }
+
+    fn build(mut ports: Vec<(Point2D, PortID)>, depth: usize) -> Option<Box<KdNode>> {
+        if ports.is_empty() {
+            return None;
+        }
+        ports.sort_by(|(first, _), (second, _)| Self::axis_value(first, depth).total_cmp(&Self::axis_value(second, depth)));
+        let mut right_ports = ports.split_off(ports.len().checked_div(2).unwrap_or_default());
+        let (pos, id) = right_ports.remove(0);
+        let next_depth = depth.saturating_add(1);
+        Some(Box::new(KdNode {pos, id, left: Self::build(ports, next_depth), right: Self::build(right_ports, next_depth)}))
+    }
+
+    fn axis_value(point: &Point2D, depth: usize) -> f64 {
+        if depth.is_multiple_of(2) { point.x } else { point.y }
+    }
+
+    /// Number of ports indexed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Finds IDs of up to k ports closest to point, closest first
+    pub fn nearest(&self, point: Point2D, k: usize) -> Vec<PortID> {
+        let mut closest: Vec<(f64, PortID)> = Vec::with_capacity(k);
+        if k > 0 {
+            Self::search(self.root.as_deref(), &point, k, 0, &mut closest);
+        }
+        closest.into_iter().map(|(_, id)| id).collect()
+    }
+
+    // keeps closest sorted by distance, holding at most k ports
+    fn search(node: Option<&KdNode>, point: &Point2D, k: usize, depth: usize, closest: &mut Vec<(f64, PortID)>) {
+        let Some(node) = node else {
+            return;
+        };
+        let distance = node.pos.distance(point);
+        if closest.len() < k || closest.last().is_some_and(|(furthest, _)| distance < *furthest) {
+            let index = closest.partition_point(|(other_distance, _)| *other_distance <= distance);
+            closest.insert(index, (distance, node.id));
+            closest.truncate(k);
+        }
+
+        let axis_difference = Self::axis_value(point, depth) - Self::axis_value(&node.pos, depth);
+        let (near, far) = if axis_difference < 0.0_f64 { (&node.left, &node.right) } else { (&node.right, &node.left) };
+        let next_depth = depth.saturating_add(1);
+        Self::search(near.as_deref(), point, k, next_depth, closest);
+        // other side of split can only hold closer ports if split is closer than furthest port found
+        if closest.len() < k || closest.last().is_some_and(|(furthest, _)| axis_difference.abs() < *furthest) {
+            Self::search(far.as_deref(), point, k, next_depth, closest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{math_utils::{get_random, seed_random}, point::Point2D, population_types::population::Population, region::{Port, PortID, Region}, transportation_graph::PortGraph};
+
+    use super::PortSpatialIndex;
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        seed_random(3);
+        let mut region = Region::new("Pangaea".to_owned(), Population::new_healthy(100));
+        let ports: Vec<Port> = (0..200).map(|id| region.add_port(PortID(id), 10, Point2D::new(get_random()*1000.0, get_random()*1000.0)).unwrap()).collect();
+        let graph = PortGraph::from_edges(ports.clone(), vec![]).unwrap();
+        let index = PortSpatialIndex::new(&graph);
+        assert_eq!(index.len(), 200);
+
+        for _ in 0..50 {
+            let point = Point2D::new(get_random()*1200.0 - 100.0, get_random()*1200.0 - 100.0);
+            let mut by_distance: Vec<&Port> = ports.iter().collect();
+            by_distance.sort_by(|first, second| first.pos.distance(&point).total_cmp(&second.pos.distance(&point)));
+            for k in [0, 1, 3, 10, 250] {
+                let expected: Vec<PortID> = by_distance.iter().take(k).map(|port| port.id).collect();
+                assert_eq!(index.nearest(point, k), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_skips_invalid_positions() {
+        let mut region = Region::new("Pangaea".to_owned(), Population::new_healthy(100));
+        let valid = region.add_port(PortID(0), 10, Point2D::new(5.0, 5.0)).unwrap();
+        // regions reject such ports, but a port's position can still be changed afterwards
+        let mut invalid = region.add_port(PortID(1), 10, Point2D::default()).unwrap();
+        invalid.pos = Point2D::new(f64::NAN, 0.0);
+        let graph = PortGraph::from_edges(vec![valid, invalid], vec![]).unwrap();
+        let index = PortSpatialIndex::new(&graph);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.nearest(Point2D::default(), 2), vec![PortID(0)]);
+
+        assert!(PortSpatialIndex::new(&PortGraph::new()).is_empty());
+        assert!(PortSpatialIndex::new(&PortGraph::new()).nearest(Point2D::default(), 3).is_empty());
+    }
+}
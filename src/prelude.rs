@@ -0,0 +1,39 @@
+// Re-exports commonly used types, so clients can write `use functionality::prelude::*;`
+
+pub use crate::{
+    config::{load_config_data, ConfigData},
+    point::Point2D,
+    population_types::{population::Population, PopulationType},
+    region::{Port, PortID, PortStatus, Region, RegionID},
+    simulation_geography::SimulationGeography,
+    transportation_allocator::{RandomTransportAllocator, RoutedAllocator, TransportAllocator, TransportJob},
+    transportation_graph::PortGraph,
+    travel_cost::{EuclideanCost, FlatCost, TravelCost},
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn prelude_scenario() {
+        let mut us: Region = Region::new("United States".to_owned(), Population::new_healthy(1000));
+        let us_port = us.add_port(PortID(0), 100, Point2D::new(0.0, 0.0)).unwrap();
+        let mut mexico: Region = Region::new("Mexico".to_owned(), Population::new_healthy(500));
+        let mexico_port = mexico.add_port(PortID(1), 100, Point2D::new(0.0, 10.0)).unwrap();
+
+        let mut graph = PortGraph::new();
+        graph.add_port(us_port.clone()).unwrap();
+        graph.add_port(mexico_port.clone()).unwrap();
+        graph.add_undirected_connection(PortID(0), PortID(1)).unwrap();
+
+        let geography = SimulationGeography::new(graph, vec![us, mexico]);
+        let allocator = RandomTransportAllocator::new(1.0);
+        let us_region = geography.get_region(us_port.region()).unwrap();
+        let dests = geography.get_open_dest_ports(PortID(0)).unwrap();
+        let jobs: Option<Vec<TransportJob>> = allocator.calculate_transport(&us_port, us_region, dests);
+        for job in jobs.unwrap_or_default() {
+            assert_eq!(job.end_port, PortID(1));
+        }
+    }
+}
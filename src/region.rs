@@ -1,10 +1,10 @@
 #![allow(dead_code)]
 
-use std::{cell::Cell, fmt::{write, Display}, sync::atomic::AtomicU32};
+use std::{cell::Cell, collections::HashMap, fmt::{write, Display}, sync::atomic::AtomicU32};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{point::{Point2D}, population_types::{population::Population, PopulationType}};
+use crate::{math_utils::random_u32_in, point::{Point2D}, population_types::{populated_area::PopulatedArea, population::Population, PopulationType}};
 
 
 
@@ -12,13 +12,30 @@ use std::cell::RefCell;
 
 
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PortID(pub u32);
 
 impl PortID {
     pub fn new(id: u32) -> Self {
         Self(id)
     }
+
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for PortID {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<PortID> for u32 {
+    fn from(id: PortID) -> Self {
+        id.0
+    }
 }
 
 impl Display for PortID {
@@ -28,6 +45,7 @@ impl Display for PortID {
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum PortStatus {
     #[default] Open,
     Closed
@@ -35,49 +53,111 @@ pub enum PortStatus {
 
 /** Represents a specific site of travel, such as an airport/seaport */
 /** Should only be constructed using an associated region */
+/** Equality includes status and status history, use same_identity to ignore them */
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Port {
     // maximum amount of transportation 
     pub capacity: u32,
     // whether port is operating or not
     status: Cell<PortStatus>,
+    // every change of status, along with the tick it occurred on
+    #[serde(default)]
+    status_history: RefCell<Vec<(u32, PortStatus)>>,
     // ID of region this port is in
     region: RegionID,
     // ID of this port
     pub id: PortID,
     // Position of this port
-    pub pos: Point2D
+    pub pos: Point2D,
+    // whether travelers are screened for infection here
+    #[serde(default)]
+    pub has_screening: bool
 }
 
 impl Port {
     /** Creates a new open port capable of transporting specified capacity */
     /** Users of Port must ensure that all Ports they create have unique IDs to avoid unwanted behavior */
     fn new(id: PortID, region: RegionID, capacity: u32, pos: Point2D) -> Self {
-        Self {capacity, status: Cell::new(PortStatus::Open), region, id, pos}
+        Self {capacity, status: Cell::new(PortStatus::Open), status_history: RefCell::new(vec![]), region, id, pos, has_screening: false}
     }
 
-    pub fn close_port(&self) {
-        self.status.replace(PortStatus::Closed);
+    /** Closes port on given tick */
+    pub fn close_port(&self, tick: u32) {
+        self.set_status(PortStatus::Closed, tick);
     }
 
     pub fn port_status(&self) -> PortStatus {
         self.status.get()
     }
 
-    pub fn set_status(&self, status: PortStatus) {
-        self.status.replace(status);
+    /** Sets status of port on given tick, recording it in the port's history if it changed */
+    pub fn set_status(&self, status: PortStatus, tick: u32) {
+        let previous_status = self.status.replace(status);
+        if previous_status != status {
+            self.status_history.borrow_mut().push((tick, status));
+        }
+    }
+
+    /** Returns every change of status, along with the tick it occurred on, from earliest to latest */
+    pub fn status_history(&self) -> Vec<(u32, PortStatus)> {
+        self.status_history.borrow().clone()
     }
 
     pub fn region(&self) -> RegionID {
         self.region
     }
+
+    /** Draws a random amount of travelers from 0 to capacity, both inclusive, without overflowing at u32::MAX */
+    /** Reproducible after seeding with seed_random */
+    pub fn sample_count(&self) -> u32 {
+        random_u32_in(0, self.capacity)
+    }
+
+    /** Restores ID, region, status and status history from original, undoing changes that must go through other paths */
+    /** Returns whether any of them had been changed */
+    pub(crate) fn restore_protected_fields(&mut self, original: &Port) -> bool {
+        let changed = self.id != original.id
+            || self.region != original.region
+            || self.port_status() != original.port_status()
+            || *self.status_history.borrow() != *original.status_history.borrow();
+        self.id = original.id;
+        self.region = original.region;
+        self.restore_status(original);
+        changed
+    }
+
+    /** Restores status and status history from original */
+    pub(crate) fn restore_status(&self, original: &Port) {
+        self.status.set(original.port_status());
+        self.status_history.replace(original.status_history.borrow().clone());
+    }
+
+    /** Checks whether ports are the same apart from their status and status history */
+    pub fn same_identity(&self, other: &Port) -> bool {
+        self.id == other.id
+            && self.region == other.region
+            && self.capacity == other.capacity
+            && self.pos == other.pos
+            && self.has_screening == other.has_screening
+    }
+
+    /** Describes port's current status and when it last changed, for debugging */
+    pub fn status_summary(&self) -> String {
+        match self.status_history.borrow().last() {
+            Some((tick, _)) => format!("Port {} in region {}: {:?} since tick {}", self.id, self.region, self.port_status(), tick),
+            None => format!("Port {} in region {}: {:?}", self.id, self.region, self.port_status()),
+        }
+    }
 }
 
 
 // Responsible for assigning a unique ID to every region
+// Only advanced by Region::new, deserialized regions keep their stored ID
 static CURRENT_REGION_ID: AtomicU32 = AtomicU32::new(0);
 
 #[derive(Debug, Clone, PartialEq, Copy, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RegionID(pub u32);
 
 impl RegionID {
@@ -85,6 +165,23 @@ impl RegionID {
         let val = CURRENT_REGION_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         RegionID(val)
     }
+
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Refers to an existing region's ID, does not reserve a new one like Region::new
+impl From<u32> for RegionID {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<RegionID> for u32 {
+    fn from(id: RegionID) -> Self {
+        id.0
+    }
 }
 
 impl Display for RegionID {
@@ -98,18 +195,25 @@ impl Display for RegionID {
 // Invariants to be preserved
 // RegionID always matched RegionID of ports it contains
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Region<P = Population> where P: PopulationType {
     id: RegionID,
     pub name: String,
     pub population: P,
-    ports: Vec<Port>
+    ports: Vec<Port>,
+    // Arbitrary labels attached to region, such as its continent
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    // Chance of each alive person giving birth to a healthy person per tick, between 0 and 1
+    #[serde(default)]
+    pub birth_rate: f64
 }
 
 impl<P> Region <P> where P: PopulationType {
     /** Creates region of people with specified population*/
     pub fn new(name: String, initial_pop: P) -> Self {
         let id = RegionID::new();
-        Region {name, population: initial_pop, ports: vec![], id }
+        Region {name, population: initial_pop, ports: vec![], id, tags: HashMap::new(), birth_rate: 0.0 }
     }
 
     pub fn id(&self) -> RegionID {
@@ -121,11 +225,18 @@ impl<P> Region <P> where P: PopulationType {
     }
 
     /** Adds port to Region and returns a copy */
-    pub fn add_port(&mut self, port_id: PortID, capacity: u32, pos: Point2D) -> Port {
+    /** Fails if Region already has a port with the same ID, or if position is not finite */
+    pub fn add_port(&mut self, port_id: PortID, capacity: u32, pos: Point2D) -> Result<Port, String> {
+        if self.get_port(port_id).is_some() {
+            return Err(format!("Port with ID {} is already in region {}", port_id, self.id));
+        }
+        if !pos.is_finite() {
+            return Err(format!("Port with ID {} cannot be placed at non-finite position {:?}", port_id, pos));
+        }
         let port = Port::new(port_id, self.id, capacity, pos);
         let clone = port.clone();
         self.ports.push(port);
-        clone
+        Ok(clone)
     }  
 
     /** Retrieves reference to port if it exists in Region */
@@ -133,17 +244,102 @@ impl<P> Region <P> where P: PopulationType {
         self.ports.iter().find(|port| port.id == id)
     }
 
-    pub fn close_ports(&mut self) {
+    /** Sets whether port screens travelers for infection, if it exists in Region */
+    /** Only changes region's copy of port, so should be done before a graph is built from region */
+    pub fn set_port_screening(&mut self, id: PortID, has_screening: bool) -> Result<(), String> {
+        let region_id = self.id;
+        let port = self.get_mut_port(id).ok_or(format!("Port with ID {} isn't in region {}", id, region_id))?;
+        port.has_screening = has_screening;
+        Ok(())
+    }
+
+    /** Retrieves mutable reference to port if it exists in Region */
+    /** Not public so that port IDs and regions cannot be changed */
+    pub(crate) fn get_mut_port(&mut self, id: PortID) -> Option<&mut Port> {
+        self.ports.iter_mut().find(|port| port.id == id)
+    }
+
+    /// Splits region in two, moving a fraction of its population and the given ports into a new region
+    /// 
+    /// The new region has a new ID and the same tags as this region
+    /// 
+    /// Returns this region and the new region, in that order
+    /// 
+    /// Only this region's copies of moved ports are given the new region's ID, so split before building a graph from the regions, or use SimulationGeography::split_region
+    /// # Errors
+    /// * Fails if fraction is not between 0 and 1
+    /// * Fails if any given port is not in this region
+    pub fn split(mut self, fraction: f64, new_name: String, ports_to_new: &[PortID]) -> Result<(Region<P>, Region<P>), String> where P: Clone {
+        if !(0.0_f64..=1.0_f64).contains(&fraction) {
+            return Err(format!("Fraction of population to split off must be between 0 and 1, not {fraction}"));
+        }
+        if let Some(missing_id) = ports_to_new.iter().find(|id| self.get_port(**id).is_none()) {
+            return Err(format!("Cannot move port with ID {} because it isn't in region {}", missing_id, self.id));
+        }
+        let population = self.population.population();
+        let new_population = population.scale(fraction);
+        let remaining_population = population.emigrate(new_population)?;
+
+        let mut new_region = Region {id: RegionID::new(), name: new_name, population: self.population.clone(), ports: vec![], tags: self.tags.clone(), birth_rate: self.birth_rate};
+        new_region.population.set_population(new_population);
+        self.population.set_population(remaining_population);
+
+        let (moved_ports, kept_ports) = self.ports.into_iter().partition(|port| ports_to_new.contains(&port.id));
+        self.ports = kept_ports;
+        new_region.ports = moved_ports;
+        for port in &mut new_region.ports {
+            port.region = new_region.id;
+        }
+        Ok((self, new_region))
+    }
+
+    /** Clones region, giving the clone and its ports a new region ID */
+    /** Port IDs are kept, so cloned ports must be given new IDs before both regions share a graph */
+    pub fn clone_with_new_id(&self) -> Region<P> where P: Clone {
+        let mut clone = self.clone();
+        clone.id = RegionID::new();
+        for port in &mut clone.ports {
+            port.region = clone.id;
+        }
+        clone
+    }
+
+    /** Renames region */
+    /** Names are not required to be unique */
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /** Attaches a label to region, replacing any previous value of the tag */
+    pub fn set_tag(&mut self, key: String, value: String) {
+        self.tags.insert(key, value);
+    }
+
+    /** Retrieves value of tag if it exists in Region */
+    pub fn get_tag(&self, key: &str) -> Option<&str> {
+        self.tags.get(key).map(|value| value.as_str())
+    }
+
+    /** Closes all ports of region on given tick */
+    pub fn close_ports(&mut self, tick: u32) {
         for port in &mut self.ports {
-            port.close_port();
+            port.close_port(tick);
         }
     }
 }
 
+impl Region<PopulatedArea> {
+    /** Creates region of people with specified population living in an area of given size */
+    /** Fails if area is not positive and finite */
+    pub fn new_populated_area(name: String, area: f32, population: Population) -> Result<Self, String> {
+        Ok(Self::new(name, PopulatedArea::new_from_area(area, population)?))
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
-    use crate::{point::Point2D, population_types::population::Population, region::PortID};
+    use crate::{point::Point2D, population_types::{population::Population, Density}, region::{PortID, PortStatus, RegionID}};
 
     use super::{Region};
     
@@ -151,8 +347,8 @@ mod tests {
     #[test]
     fn region_find_port_test() {
         let mut country = Region::new("Super".to_owned(), Population::new_healthy(100));
-        let small_port = country.add_port(PortID(0), 100, Point2D::default());
-        let big_port = country.add_port(PortID(1), 1000, Point2D::default());
+        let small_port = country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let big_port = country.add_port(PortID(1), 1000, Point2D::default()).unwrap();
 
         assert!(country.get_port(PortID::new(0)).is_some());
         assert!(country.get_port(PortID::new(1)).is_some());
@@ -165,9 +361,9 @@ mod tests {
         let mut country = Region::new("Super".to_owned(), Population::new_healthy(100));
         let mut big_country = Region::new("Mega".to_owned(), Population::new_healthy(1_000_000));
 
-        let small_port = country.add_port(PortID::new(0), 100, Point2D::default());
-        let big_port = country.add_port(PortID::new(1), 1000, Point2D::default());
-        let huge_port = big_country.add_port(PortID::new(2), 10_000_000, Point2D::default());
+        let small_port = country.add_port(PortID::new(0), 100, Point2D::default()).unwrap();
+        let big_port = country.add_port(PortID::new(1), 1000, Point2D::default()).unwrap();
+        let huge_port = big_country.add_port(PortID::new(2), 10_000_000, Point2D::default()).unwrap();
 
 
         // make sure countries have unique ID
@@ -182,5 +378,184 @@ mod tests {
             assert_eq!(port.region, big_country.id)
         }
     }
-}
 
+    #[test]
+    fn region_deserialize_keeps_id() {
+        let json = r#"{"id":7,"name":"Atlantis","population":{"healthy":100,"infected":0,"dead":0,"recovered":0},"ports":[]}"#;
+        let region: Region = serde_json::from_str(json).unwrap();
+        assert_eq!(region.id(), RegionID(7));
+        assert_eq!(region.name, "Atlantis");
+    }
+
+    #[test]
+    fn id_conversions() {
+        assert_eq!(PortID::from(3), PortID(3));
+        assert_eq!(u32::from(PortID(3)), 3);
+        assert_eq!(PortID(7).as_u32(), 7);
+        let port_id: PortID = 12.into();
+        let raw: u32 = port_id.into();
+        assert_eq!(PortID::from(raw), port_id);
+
+        assert_eq!(RegionID::from(5), RegionID(5));
+        assert_eq!(u32::from(RegionID(5)), 5);
+        let region = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        assert_eq!(RegionID::from(region.id().as_u32()), region.id());
+        let raw: u32 = region.id().into();
+        assert_eq!(raw, region.id().0);
+    }
+
+    #[test]
+    fn port_sample_count() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let huge_port = country.add_port(PortID(0), u32::MAX, Point2D::default()).unwrap();
+        let empty_port = country.add_port(PortID(1), 0, Point2D::default()).unwrap();
+        let small_port = country.add_port(PortID(2), 3, Point2D::default()).unwrap();
+        for _ in 0..1000 {
+            huge_port.sample_count();
+            assert_eq!(empty_port.sample_count(), 0);
+            assert!(small_port.sample_count() <= 3);
+        }
+        // full capacity can be drawn
+        assert!((0..1000).any(|_| small_port.sample_count() == 3));
+    }
+
+    #[test]
+    fn port_same_identity() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let port = country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let other_port = country.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        let copy = port.clone();
+        assert!(port == copy && port.same_identity(&copy));
+
+        // status differs, but identity is the same
+        copy.close_port(4);
+        assert_ne!(port, copy);
+        assert!(port.same_identity(&copy));
+        assert_eq!(copy.status_summary(), format!("Port 0 in region {}: Closed since tick 4", country.id()));
+        assert_eq!(port.status_summary(), format!("Port 0 in region {}: Open", country.id()));
+
+        assert!(!port.same_identity(&other_port));
+        let mut moved = port.clone();
+        moved.pos = Point2D::new(1.0, 0.0);
+        assert!(!port.same_identity(&moved));
+    }
+
+    #[test]
+    fn region_tags() {
+        let mut country = Region::new("Super".to_owned(), Population::new_healthy(100));
+        assert_eq!(country.get_tag("continent"), None);
+
+        country.set_tag("continent".to_owned(), "Asia".to_owned());
+        assert_eq!(country.get_tag("continent"), Some("Asia"));
+
+        // overwrite tag
+        country.set_tag("continent".to_owned(), "Europe".to_owned());
+        assert_eq!(country.get_tag("continent"), Some("Europe"));
+        assert_eq!(country.get_tag("income"), None);
+
+        // tags are optional when deserializing
+        let json = r#"{"id":7,"name":"Atlantis","population":{"healthy":100,"infected":0,"dead":0,"recovered":0},"ports":[]}"#;
+        let region: Region = serde_json::from_str(json).unwrap();
+        assert_eq!(region.get_tag("continent"), None);
+    }
+
+    #[test]
+    fn port_status_history() {
+        let mut country = Region::new("Super".to_owned(), Population::new_healthy(100));
+        let port = country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        assert!(port.status_history().is_empty());
+
+        port.close_port(3);
+        // setting same status again is not a transition
+        port.set_status(PortStatus::Closed, 4);
+        port.set_status(PortStatus::Open, 10);
+
+        assert_eq!(port.port_status(), PortStatus::Open);
+        assert_eq!(port.status_history(), vec![(3, PortStatus::Closed), (10, PortStatus::Open)]);
+    }
+
+    #[test]
+    fn region_split() {
+        let mut country = Region::new("Super".to_owned(), Population {healthy: 1001, infected: 51, dead: 7, recovered: 3});
+        country.set_tag("continent".to_owned(), "Asia".to_owned());
+        country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        country.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        country.add_port(PortID(2), 100, Point2D::default()).unwrap();
+        let original_population = country.population;
+        let original_id = country.id;
+
+        // invalid splits
+        assert!(country.clone().split(1.5, "Mega".to_owned(), &[]).is_err());
+        assert!(country.clone().split(0.5, "Mega".to_owned(), &[PortID(5)]).is_err());
+
+        let (old_country, new_country) = country.split(0.5, "Mega".to_owned(), &[PortID(1), PortID(2)]).unwrap();
+        assert_eq!(old_country.population + new_country.population, original_population);
+        assert_eq!(old_country.id, original_id);
+        assert_ne!(new_country.id, original_id);
+        assert_eq!(new_country.name, "Mega");
+        assert_eq!(new_country.get_tag("continent"), Some("Asia"));
+
+        let old_port_ids: Vec<PortID> = old_country.ports.iter().map(|port| port.id).collect();
+        let new_port_ids: Vec<PortID> = new_country.ports.iter().map(|port| port.id).collect();
+        assert_eq!(old_port_ids, vec![PortID(0)]);
+        assert_eq!(new_port_ids, vec![PortID(1), PortID(2)]);
+        for port in old_country.ports {
+            assert_eq!(port.region, old_country.id);
+        }
+        for port in new_country.ports {
+            assert_eq!(port.region, new_country.id);
+        }
+    }
+
+    #[test]
+    fn region_new_populated_area() {
+        let population = Population {healthy: 600, infected: 200, dead: 200, recovered: 0};
+        let country = Region::new_populated_area("Atlantis".to_owned(), 20.0, population).unwrap();
+        assert_eq!(country.population.get_population(), population);
+        assert_eq!(country.population.total_density(), 50.0);
+        assert_eq!(country.population.alive_density(), 40.0);
+        assert_eq!(Density::total_density(&country.population), 50.0);
+
+        assert!(Region::new_populated_area("Atlantis".to_owned(), 0.0, population).is_err());
+    }
+
+    #[test]
+    fn region_clone_with_new_id() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        country.add_port(PortID(1), 50, Point2D::new(1.0, 2.0)).unwrap();
+        country.set_tag("continent".to_owned(), "Ocean".to_owned());
+
+        let clone = country.clone_with_new_id();
+        assert_ne!(clone.id(), country.id());
+        assert_eq!(clone.name, country.name);
+        assert_eq!(clone.population, country.population);
+        assert_eq!(clone.get_tag("continent"), Some("Ocean"));
+        assert_eq!(clone.get_ports().len(), 2);
+        for port in clone.get_ports() {
+            assert_eq!(port.region(), clone.id());
+            assert_eq!(port.capacity, country.get_port(port.id).unwrap().capacity);
+        }
+        assert!(country.get_ports().iter().all(|port| port.region() == country.id()));
+    }
+
+    #[test]
+    fn region_duplicate_port_id() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        assert!(country.add_port(PortID(0), 50, Point2D::new(1.0, 2.0)).is_err());
+        assert_eq!(country.get_ports().len(), 1);
+        assert_eq!(country.get_port(PortID(0)).unwrap().capacity, 100);
+        assert!(country.add_port(PortID(1), 50, Point2D::new(1.0, 2.0)).is_ok());
+    }
+
+    #[test]
+    fn region_non_finite_port_position() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        assert!(country.add_port(PortID(0), 100, Point2D::new(f64::NAN, 0.0)).is_err());
+        assert!(country.add_port(PortID(0), 100, Point2D::new(0.0, f64::INFINITY)).is_err());
+        assert!(country.add_port(PortID(0), 100, Point2D::new(f64::NEG_INFINITY, f64::NAN)).is_err());
+        assert!(country.get_ports().is_empty());
+        assert!(country.add_port(PortID(0), 100, Point2D::new(-3.0, 1e300)).is_ok());
+    }
+}
@@ -1,6 +1,6 @@
-use std::{fmt::format, slice::Iter};
+use std::{collections::{hash_map::Values, HashMap, HashSet, VecDeque}, fmt::format};
 
-use crate::{point::{ Point2D}, population_types::{population::Population, PopulationType}, region::{Port, PortID, Region, RegionID}, transportation_graph::PortGraph};
+use crate::{math_utils::binomial_sample, point::{ Point2D}, population_types::{population::Population, PopulationType}, region::{Port, PortID, PortStatus, Region, RegionID}, transportation_allocator::TransportJob, transportation_graph::PortGraph};
 
 /// Responsible for storing simulation geography data and communicating changes across its components
 /// 
@@ -8,22 +8,76 @@ use crate::{point::{ Point2D}, population_types::{population::Population, Popula
 /// 
 /// Assumes that every port in all the regions has a unique ID
 /// 
+/// Assumes that every region has a unique ID
+/// 
+/// new only checks these IDs in debug builds, so release builds silently accept duplicates, use from_regions to always check them
+/// 
+/// Regions are stored by ID, so iterating over regions or their IDs visits them in no particular order, sort by ID where order matters
+/// 
 /// Assumes that all ports contained in the regions are the same as all the ports in the graph and have the same state
+/// 
+/// Does not own people travelling between regions, so populations only count people living in regions
+/// 
+/// Clones are independent copies, including port statuses, so they can be used for parameter sweeps
+#[derive(Clone)]
 pub struct SimulationGeography<P: PopulationType> where P: PopulationType {
     graph: PortGraph,
-    regions: Vec<Region<P>>
+    regions: HashMap<RegionID, Region<P>>
 }
 
 // Invariants:
 // If a port with a certain ID exists in both graph and regions, their states must be equal
 // Every port contained within the regions must be contained in the graph and vice versa
 impl<P> SimulationGeography <P> where P: PopulationType {
+    /// Panics in debug builds if two regions share an ID or hold ports with the same ID
     pub fn new(graph: PortGraph, regions: Vec<Region<P>>) -> Self {
+        if cfg!(debug_assertions) {
+            if let Err(message) = Self::check_unique_region_ids(&regions).and_then(|_| Self::check_unique_port_ids(&regions)) {
+                panic!("{}", message);
+            }
+        }
+        let regions = regions.into_iter().map(|region| (region.id(), region)).collect();
         Self { graph, regions }
     }
 
+    /* Creates geography whose graph contains every port of the given regions, connected by given directed edges */
+    /* Fails if two regions or two ports share an ID, or an edge cannot be added */
+    pub fn from_regions(regions: Vec<Region<P>>, edges: Vec<(PortID, PortID)>) -> Result<Self, String> {
+        Self::check_unique_region_ids(&regions)?;
+        Self::check_unique_port_ids(&regions)?;
+        let ports = regions.iter().flat_map(|region| region.get_ports().iter().cloned()).collect();
+        let graph = PortGraph::from_edges(ports, edges)?;
+        Ok(Self::new(graph, regions))
+    }
+
+    /* Checks that no two of the given regions share an ID, which can happen with regions loaded from a config */
+    /* Fails naming the first duplicated ID */
+    pub fn check_unique_region_ids(regions: &[Region<P>]) -> Result<(), String> {
+        let mut region_ids: HashSet<RegionID> = HashSet::new();
+        for region in regions {
+            if !region_ids.insert(region.id()) {
+                return Err(format!("Region ID {} is used by more than one region", region.id()));
+            }
+        }
+        Ok(())
+    }
+
+    /* Checks that no port ID is held by more than one port across the given regions */
+    /* Fails naming the first duplicated ID and the regions holding it */
+    pub fn check_unique_port_ids(regions: &[Region<P>]) -> Result<(), String> {
+        let mut owners: HashMap<PortID, RegionID> = HashMap::new();
+        for region in regions {
+            for port in region.get_ports() {
+                if let Some(owner) = owners.insert(port.id, region.id()) {
+                    return Err(format!("Port ID {} is used in both region {} and region {}", port.id, owner, region.id()));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn find_port_in_regions(&self, port_id: PortID) -> Option<&Port> {
-        for region in &self.regions {
+        for region in self.regions.values() {
             let result = region.get_ports().iter().find(|port| port.id == port_id);
             if result.is_some() {
                 return result;
@@ -34,11 +88,48 @@ impl<P> SimulationGeography <P> where P: PopulationType {
 
     /* Find region with given ID, if it exists */
     pub fn get_region(&self, region_id: RegionID) -> Option<&Region<P>> {
-        self.regions.iter().find(|region| region.id() == region_id)
+        self.regions.get(&region_id)
+    }
+
+    /// Finds region with given name, if it exists
+    /// 
+    /// Names are not guaranteed to be unique, so the first match is returned, checking regions in order of ID
+    pub fn get_region_by_name(&self, name: &str) -> Option<&Region<P>> {
+        self.regions.values()
+            .filter(|region| region.name == name)
+            .min_by_key(|region| region.id())
     }
 
     fn get_region_mut(&mut self, region_id: RegionID) -> Option<&mut Region<P>> {
-        self.regions.iter_mut().find(|region| region.id() == region_id)
+        self.regions.get_mut(&region_id)
+    }
+
+    /// Applies given changes to region, if found
+    /// 
+    /// Changes must not add ports to region, since they will not be added to the graph
+    /// # Errors
+    /// * Fails if region ID not found
+    pub fn with_region_mut<F>(&mut self, region_id: RegionID, f: F) -> Result<(), String> where F: FnOnce(&mut Region<P>) {
+        self.get_region_mut(region_id).map(f).ok_or(format!("Cannot find region ID {}", region_id))
+    }
+
+    /// Splits region in two, see Region::split, updating the graph's copies of moved ports to belong to the new region
+    /// 
+    /// Returns ID of the new region
+    /// # Errors
+    /// * Fails without changing geography if region ID not found or region cannot be split
+    pub fn split_region(&mut self, region_id: RegionID, fraction: f64, new_name: String, ports_to_new: &[PortID]) -> Result<RegionID, String> where P: Clone {
+        let region = self.get_region(region_id).ok_or(format!("Cannot find region ID {}", region_id))?.clone();
+        let (old_region, new_region) = region.split(fraction, new_name, ports_to_new)?;
+        let new_region_id = new_region.id();
+        for port in new_region.get_ports() {
+            if let Some(graph_port) = self.graph.get_mut_port(port.id) {
+                *graph_port = port.clone();
+            }
+        }
+        self.regions.insert(region_id, old_region);
+        self.regions.insert(new_region_id, new_region);
+        Ok(new_region_id)
     }
 
     /* Find port with given ID, if it exists */
@@ -53,6 +144,11 @@ impl<P> SimulationGeography <P> where P: PopulationType {
         region.map(|some_region| &some_region.population)
     }
 
+    /* Returns populations of all contained regions, keyed by region ID */
+    pub fn population_snapshot(&self) -> HashMap<RegionID, Population> {
+        self.regions.iter().map(|(id, region)| (*id, region.population.population())).collect()
+    }
+
     /* Set population of specified region, if it exists */
     pub fn set_population(&mut self, region_id: RegionID, population: Population) -> Result<(), String> {
         self.get_region_mut(region_id).map(|region| region.population.set_population(population)).ok_or(format!("Cannot find region ID {}", region_id))
@@ -99,14 +195,177 @@ impl<P> SimulationGeography <P> where P: PopulationType {
         }
     }
 
-    /* Returns contained regions */
-    pub fn get_regions(&self) -> Iter<'_, Region<P>> {
-        self.regions.iter()
+    /// Converts healthy people of region into infected people, if found
+    /// 
+    /// Infects at most as many people as there are healthy people in the region
+    /// 
+    /// Returns new population of region
+    /// # Errors
+    /// * Fails if region ID not found
+    pub fn infect_region(&mut self, region_id: RegionID, count: u32) -> Result<Population, String> {
+        let region = self.get_region_mut(region_id);
+        match region {
+            Some(unwrapped_region) => {
+                let mut new_pop = unwrapped_region.population.population();
+                let newly_infected = count.min(new_pop.healthy);
+                new_pop.healthy = new_pop.healthy.saturating_sub(newly_infected);
+                new_pop.infected = new_pop.infected.saturating_add(newly_infected);
+                unwrapped_region.population.set_population(new_pop);
+                Ok(new_pop)
+            },
+            None => Err(format!("Cannot find region ID {}", region_id)),
+        }
+    }
+
+    /// Spreads infection among travellers waiting together at each open port, before they depart
+    /// 
+    /// jobs are the transports about to leave, whose populations are changed, while regions' populations are left alone
+    /// 
+    /// Healthy travellers are infected with a chance of infectivity, scaled by how much of their port's capacity is used and how much of everyone leaving through it is infected
+    /// 
+    /// Jobs leaving from closed ports or ports not in the geography are left alone
+    /// 
+    /// Opt-in: not applied unless called
+    /// # Errors
+    /// * Fails without changing any job if infectivity is not between 0 and 1
+    pub fn apply_port_mixing(&self, infectivity: f64, jobs: &mut [TransportJob]) -> Result<(), String> {
+        if !(0.0_f64..=1.0_f64).contains(&infectivity) {
+            return Err(format!("Infectivity must be between 0 and 1, not {infectivity}"));
+        }
+        let mut cohorts: HashMap<PortID, Population> = HashMap::new();
+        for job in jobs.iter() {
+            let cohort = cohorts.entry(job.start_port).or_default();
+            *cohort = cohort.saturating_add(job.population);
+        }
+        for job in jobs.iter_mut() {
+            let Some(port) = self.get_port(job.start_port).filter(|port| port.port_status() == PortStatus::Open && port.capacity > 0) else {
+                continue;
+            };
+            let cohort = cohorts[&job.start_port];
+            let alive = cohort.get_alive();
+            if alive == 0 {
+                continue;
+            }
+            let utilization = (alive.min(port.capacity) as f64)/port.capacity as f64;
+            let infected_fraction = cohort.infected as f64/alive as f64;
+            let new_infections = binomial_sample(job.population.healthy, infectivity*utilization*infected_fraction);
+            job.population.healthy = job.population.healthy.saturating_sub(new_infections);
+            job.population.infected = job.population.infected.saturating_add(new_infections);
+        }
+        Ok(())
+    }
+
+    /// Moves every region's population on to the next tick, see PopulationType::advance_tick
+    /// 
+    /// Returns populations before and after advancing of every region whose population changed
+    pub fn advance_populations(&mut self) -> HashMap<RegionID, (Population, Population)> {
+        let mut changes = HashMap::new();
+        for region in self.regions.values_mut() {
+            let old_population = region.population.population();
+            region.population.advance_tick();
+            let new_population = region.population.population();
+            if old_population != new_population {
+                changes.insert(region.id(), (old_population, new_population));
+            }
+        }
+        changes
+    }
+
+    /// Adds healthy newborns to every region, each alive person giving birth with a chance of the region's birth rate
+    /// 
+    /// Opt-in: not applied unless called
+    /// # Errors
+    /// * Fails without changing any region if a birth rate is not between 0 and 1
+    pub fn apply_births(&mut self) -> Result<(), String> {
+        if let Some(region) = self.regions.values().find(|region| !(0.0_f64..=1.0_f64).contains(&region.birth_rate)) {
+            return Err(format!("Birth rate of region {} must be between 0 and 1, not {}", region.id(), region.birth_rate));
+        }
+        for region in self.regions.values_mut() {
+            let mut population = region.population.population();
+            let births = binomial_sample(population.get_alive(), region.birth_rate);
+            population.healthy = population.healthy.saturating_add(births);
+            region.population.set_population(population);
+        }
+        Ok(())
     }
 
-    /* Returns IDs of contained regions */
+    /* Returns contained regions, in no particular order */
+    pub fn get_regions(&self) -> Values<'_, RegionID, Region<P>> {
+        self.regions.values()
+    }
+
+    /* Returns contained regions with the given tag value */
+    pub fn regions_with_tag(&self, key: &str, value: &str) -> Vec<&Region<P>> {
+        self.regions.values().filter(|region| region.get_tag(key) == Some(value)).collect()
+    }
+
+    /* Sums populations of contained regions sharing each value of the given tag, ignoring untagged regions */
+    pub fn population_by_tag(&self, key: &str) -> HashMap<String, Population> {
+        let mut populations: HashMap<String, Population> = HashMap::new();
+        for region in self.regions.values() {
+            if let Some(value) = region.get_tag(key) {
+                let total = populations.entry(value.to_owned()).or_default();
+                *total = total.saturating_add(region.population.population());
+            }
+        }
+        populations
+    }
+
+    /* Returns number of ports in graph */
+    pub fn port_count(&self) -> usize {
+        self.graph.port_count()
+    }
+
+    /* Returns number of contained regions */
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    /* Checks whether geography has no regions and no ports */
+    pub fn is_empty(&self) -> bool {
+        self.region_count() == 0 && self.port_count() == 0
+    }
+
+    /// Describes ports and their connections in Graphviz DOT format, for debugging connectivity
+    /// 
+    /// Each port is labeled with its ID and status, and each directed connection is an edge, both in order of port ID
+    pub fn to_dot(&self) -> String {
+        let mut ports: Vec<&Port> = self.graph.iter_ports().collect();
+        ports.sort_by_key(|port| port.id);
+        let mut dot = String::from("digraph ports {\n");
+        for port in &ports {
+            dot.push_str(&format!("    {} [label=\"Port {}\\n{:?}\"];\n", port.id, port.id, port.port_status()));
+        }
+        for port in &ports {
+            let mut dests: Vec<PortID> = self.graph.get_dest_ports(port.id).unwrap_or_default().iter().map(|dest| dest.id).collect();
+            dests.sort();
+            for dest in dests {
+                dot.push_str(&format!("    {} -> {};\n", port.id, dest));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /* Returns IDs of contained regions, in no particular order */
     pub fn get_region_ids(&self) -> Vec<RegionID> {
-        self.regions.iter().map(|reg| reg.id()).collect()
+        self.regions.keys().copied().collect()
+    }
+
+    /* Checks whether any region still has an active infection */
+    /* People in transit are not included, since geography does not own them, so check the simulation instead when travellers matter */
+    pub fn any_active_infection(&self) -> bool {
+        self.regions.values().any(|region| region.population.population().has_active_infection())
+    }
+
+    /* Returns IDs of regions with at least one infected person, sorted by ID */
+    pub fn infected_regions(&self) -> Vec<RegionID> {
+        let mut region_ids: Vec<RegionID> = self.regions.values()
+            .filter(|region| region.population.population().infected > 0)
+            .map(|region| region.id())
+            .collect();
+        region_ids.sort();
+        region_ids
     }
 
     /* Returns contained ports */
@@ -114,6 +373,11 @@ impl<P> SimulationGeography <P> where P: PopulationType {
         self.graph.get_ports()
     }
 
+    /* Iterates over contained ports without allocating */
+    pub fn iter_ports(&self) -> impl Iterator<Item = &Port> {
+        self.graph.iter_ports()
+    }
+
     /* Gets possible destination ports of a port, if it exists */
     pub fn get_all_dest_ports(&self, id: PortID) -> Option<Vec<&Port>> {
        self.graph.get_dest_ports(id)
@@ -124,8 +388,118 @@ impl<P> SimulationGeography <P> where P: PopulationType {
         self.graph.get_open_dest_ports(id)
     }
 
-    /* Closes port with given ID, if it exists  */
-    pub fn close_port(&mut self, port_id: PortID) -> Result<(), String>{
+    /// Finds all other regions that can be travelled to from given region through any number of port connections
+    /// 
+    /// Connections are followed regardless of whether ports are open or closed
+    /// 
+    /// Returns an empty set if region not found
+    pub fn connected_regions(&self, from: RegionID) -> HashSet<RegionID> {
+        let mut visited_ports: HashSet<PortID> = HashSet::new();
+        let mut ports_to_visit: VecDeque<PortID> = VecDeque::new();
+        if let Some(region) = self.get_region(from) {
+            for port in region.get_ports() {
+                visited_ports.insert(port.id);
+                ports_to_visit.push_back(port.id);
+            }
+        }
+        let mut reached_regions: HashSet<RegionID> = HashSet::new();
+        while let Some(port_id) = ports_to_visit.pop_front() {
+            for dest in self.graph.get_dest_ports(port_id).unwrap_or_default() {
+                if visited_ports.insert(dest.id) {
+                    reached_regions.insert(dest.region());
+                    ports_to_visit.push_back(dest.id);
+                }
+            }
+        }
+        reached_regions.remove(&from);
+        reached_regions
+    }
+
+    /* Sets status of every port on given tick, in both regions and graph */
+    pub fn set_all_ports_status(&mut self, status: PortStatus, tick: u32) {
+        for region in self.regions.values() {
+            for port in region.get_ports() {
+                port.set_status(status, tick);
+            }
+        }
+        for port in self.graph.iter_ports() {
+            port.set_status(status, tick);
+        }
+    }
+
+    /* Moves port with given ID to given position, in both its region and the graph */
+    /* Nothing is moved if port is missing from either */
+    pub fn move_port(&mut self, port_id: PortID, pos: Point2D) -> Result<(), String> {
+        if !self.graph.in_graph(port_id) {
+            return Err(format!("Cannot move port with ID {} because it wasn't found in graph", port_id.0));
+        }
+        let region_port = self.regions.values_mut().find_map(|region| region.get_mut_port(port_id));
+        match region_port {
+            Some(region_port) => region_port.pos = pos,
+            None => return Err(format!("Cannot move port with ID {} because it wasn't found in any region", port_id.0)),
+        }
+        if let Some(graph_port) = self.graph.get_mut_port(port_id) {
+            graph_port.pos = pos;
+        }
+        Ok(())
+    }
+
+    /// Applies given changes to port, if found in both a region and the graph
+    /// 
+    /// Changes are made to the region's port, then copied to the graph's port
+    /// 
+    /// Changes must not change port's ID, region or status, use set_port_status to change status
+    /// # Errors
+    /// * Fails if port is missing from regions or graph
+    /// * Fails if changes changed port's ID, region, status or status history, in which case those are restored but other changes remain
+    pub fn with_port_mut<F>(&mut self, port_id: PortID, f: F) -> Result<(), String> where F: FnOnce(&mut Port) {
+        if !self.graph.in_graph(port_id) {
+            return Err(format!("Cannot change port with ID {} because it wasn't found in graph", port_id.0));
+        }
+        let region_port = self.regions.values_mut().find_map(|region| region.get_mut_port(port_id))
+            .ok_or(format!("Cannot change port with ID {} because it wasn't found in any region", port_id.0))?;
+        let original = region_port.clone();
+        f(region_port);
+        let protected_changed = region_port.restore_protected_fields(&original);
+        let changed_port = region_port.clone();
+        if let Some(graph_port) = self.graph.get_mut_port(port_id) {
+            *graph_port = changed_port;
+        }
+        if protected_changed {
+            Err(format!("Cannot change ID, region or status of port with ID {}", port_id.0))
+        } else {
+            Ok(())
+        }
+    }
+
+    /* Sets status of port with given ID on given tick, in both its region and the graph */
+    pub fn set_port_status(&mut self, port_id: PortID, status: PortStatus, tick: u32) -> Result<(), String> {
+        let region_port = self.find_port_in_regions(port_id).ok_or(format!("Cannot set status of port with ID {} because it wasn't found in any region", port_id.0))?;
+        let graph_port = self.graph.get_port(port_id).ok_or(format!("Cannot set status of port with ID {} because it wasn't found in graph", port_id.0))?;
+        region_port.set_status(status, tick);
+        graph_port.set_status(status, tick);
+        Ok(())
+    }
+
+    /// Restores status and status history of every given port from the given copies, e.g. ones taken with get_ports before statuses changed
+    /// # Errors
+    /// * Fails without changing any port if a port is missing from regions or graph
+    pub fn restore_port_statuses(&mut self, ports: &[Port]) -> Result<(), String> {
+        let mut found_ports = vec![];
+        for original in ports {
+            let region_port = self.find_port_in_regions(original.id).ok_or(format!("Cannot restore status of port with ID {} because it wasn't found in any region", original.id.0))?;
+            let graph_port = self.graph.get_port(original.id).ok_or(format!("Cannot restore status of port with ID {} because it wasn't found in graph", original.id.0))?;
+            found_ports.push((original, region_port, graph_port));
+        }
+        for (original, region_port, graph_port) in found_ports {
+            region_port.restore_status(original);
+            graph_port.restore_status(original);
+        }
+        Ok(())
+    }
+
+    /* Closes port with given ID on given tick, if it exists  */
+    pub fn close_port(&mut self, port_id: PortID, tick: u32) -> Result<(), String>{
         let region_port = self.find_port_in_regions(port_id);
         let graph_port = self.graph.get_port(port_id);
         if region_port.is_none() {
@@ -133,9 +507,543 @@ impl<P> SimulationGeography <P> where P: PopulationType {
         } else if graph_port.is_none() {
             Err(format!("Cannot close port with ID {} because it wasn't found in graph", port_id.0))
         } else {
-            region_port.unwrap().close_port();
-            graph_port.unwrap().close_port();
+            region_port.unwrap().close_port(tick);
+            graph_port.unwrap().close_port(tick);
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{math_utils::seed_random, point::Point2D, population_types::{population::Population, temporary_immunity_population::TemporaryImmunityPopulation, PopulationType}, region::{Port, PortID, PortStatus, Region, RegionID}, transportation_graph::PortGraph};
+
+    use std::collections::{HashMap, HashSet};
+
+    use crate::transportation_allocator::TransportJob;
+
+    use super::SimulationGeography;
+
+    #[test]
+    fn infect_region() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let china_port = china.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut graph = PortGraph::new();
+        graph.add_port(china_port).unwrap();
+        let mut geography = SimulationGeography::new(graph, vec![china]);
+
+        let new_pop = geography.infect_region(china_id, 30).unwrap();
+        assert_eq!(new_pop, Population {healthy: 70, infected: 30, dead: 0, recovered: 0});
+        assert_eq!(*geography.get_population(china_id).unwrap(), new_pop);
+
+        // cannot infect more people than there are healthy people
+        let new_pop = geography.infect_region(china_id, 1000).unwrap();
+        assert_eq!(new_pop, Population {healthy: 0, infected: 100, dead: 0, recovered: 0});
+
+        // nonexistent region
+        assert!(geography.infect_region(RegionID(9999), 1).is_err());
+    }
+
+    #[test]
+    fn regions_with_tag() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(100));
+        china.set_tag("continent".to_owned(), "Asia".to_owned());
+        let mut japan = Region::new("Japan".to_owned(), Population::new_healthy(100));
+        japan.set_tag("continent".to_owned(), "Asia".to_owned());
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(100));
+        benin.set_tag("continent".to_owned(), "Africa".to_owned());
+        let atlantis = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let geography = SimulationGeography::new(PortGraph::new(), vec![china, japan, benin, atlantis]);
+
+        let mut asian_names: Vec<&str> = geography.regions_with_tag("continent", "Asia").iter().map(|region| region.name.as_str()).collect();
+        asian_names.sort();
+        assert_eq!(asian_names, vec!["China", "Japan"]);
+        let african_names: Vec<&str> = geography.regions_with_tag("continent", "Africa").iter().map(|region| region.name.as_str()).collect();
+        assert_eq!(african_names, vec!["Benin"]);
+        assert!(geography.regions_with_tag("continent", "Europe").is_empty());
+        assert!(geography.regions_with_tag("income", "High").is_empty());
+    }
+
+    #[test]
+    fn population_by_tag() {
+        let mut china = Region::new("China".to_owned(), Population {healthy: 100, infected: 10, dead: 1, recovered: 0});
+        china.set_tag("continent".to_owned(), "Asia".to_owned());
+        let mut japan = Region::new("Japan".to_owned(), Population {healthy: 50, infected: 5, dead: 0, recovered: 2});
+        japan.set_tag("continent".to_owned(), "Asia".to_owned());
+        let mut benin = Region::new("Benin".to_owned(), Population {healthy: 30, infected: 3, dead: 0, recovered: 0});
+        benin.set_tag("continent".to_owned(), "Africa".to_owned());
+        let atlantis = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let geography = SimulationGeography::new(PortGraph::new(), vec![china, japan, benin, atlantis]);
+
+        let populations = geography.population_by_tag("continent");
+        assert_eq!(populations.len(), 2);
+        assert_eq!(populations["Asia"], Population {healthy: 150, infected: 15, dead: 1, recovered: 2});
+        assert_eq!(populations["Africa"], Population {healthy: 30, infected: 3, dead: 0, recovered: 0});
+        assert!(geography.population_by_tag("income").is_empty());
+
+        // totals saturate instead of overflowing
+        let mut huge = Region::new("Huge".to_owned(), Population::new_healthy(u32::MAX));
+        huge.set_tag("continent".to_owned(), "Asia".to_owned());
+        let mut also_huge = Region::new("Also Huge".to_owned(), Population::new_healthy(u32::MAX));
+        also_huge.set_tag("continent".to_owned(), "Asia".to_owned());
+        let geography = SimulationGeography::new(PortGraph::new(), vec![huge, also_huge]);
+        assert_eq!(geography.population_by_tag("continent")["Asia"], Population::new_healthy(u32::MAX));
+    }
+
+    #[test]
+    fn population_snapshot() {
+        let china = Region::new("China".to_owned(), Population {healthy: 100, infected: 10, dead: 1, recovered: 0});
+        let benin = Region::new("Benin".to_owned(), Population {healthy: 30, infected: 3, dead: 0, recovered: 0});
+        let geography = SimulationGeography::new(PortGraph::new(), vec![china, benin]);
+
+        let snapshot = geography.population_snapshot();
+        assert_eq!(snapshot.len(), 2);
+        for region_id in geography.get_region_ids() {
+            assert_eq!(snapshot[&region_id], *geography.get_population(region_id).unwrap());
+        }
+    }
+
+    #[test]
+    fn region_lookup() {
+        let china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let benin = Region::new("Benin".to_owned(), Population::new_healthy(30));
+        let atlantis = Region::new("Atlantis".to_owned(), Population::new_healthy(5));
+        let mut expected_ids = vec![china.id(), benin.id(), atlantis.id()];
+        let mut geography = SimulationGeography::new(PortGraph::new(), vec![china, benin, atlantis]);
+
+        for region_id in &expected_ids {
+            assert_eq!(geography.get_region(*region_id).unwrap().id(), *region_id);
+        }
+        assert!(geography.get_region(RegionID(9999)).is_none());
+
+        // mutations are applied to correct region
+        geography.add_population(expected_ids[1], Population::new_healthy(10)).unwrap();
+        assert_eq!(geography.get_population(expected_ids[1]).unwrap().healthy, 40);
+        assert_eq!(geography.get_population(expected_ids[0]).unwrap().healthy, 100);
+
+        // all regions still iterable
+        let mut region_ids: Vec<RegionID> = geography.get_regions().map(|region| region.id()).collect();
+        region_ids.sort();
+        expected_ids.sort();
+        assert_eq!(region_ids, expected_ids);
+    }
+
+    #[test]
+    fn connected_regions() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let china_port = china.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(100));
+        let benin_port1 = benin.add_port(PortID(1), 10, Point2D::default()).unwrap();
+        let benin_port2 = benin.add_port(PortID(2), 10, Point2D::default()).unwrap();
+        let mut atlantis = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let atlantis_port = atlantis.add_port(PortID(3), 10, Point2D::default()).unwrap();
+        let (china_id, benin_id, atlantis_id) = (china.id(), benin.id(), atlantis.id());
+
+        let mut graph = PortGraph::from_edges(vec![china_port, benin_port1, benin_port2, atlantis_port], vec![]).unwrap();
+        graph.add_undirected_connection(PortID(0), PortID(1)).unwrap();
+        graph.add_undirected_connection(PortID(1), PortID(2)).unwrap();
+        let geography = SimulationGeography::new(graph, vec![china, benin, atlantis]);
+
+        assert_eq!(geography.connected_regions(china_id), HashSet::from([benin_id]));
+        assert_eq!(geography.connected_regions(benin_id), HashSet::from([china_id]));
+        assert!(geography.connected_regions(atlantis_id).is_empty());
+        assert!(geography.connected_regions(RegionID(9999)).is_empty());
+    }
+
+    #[test]
+    fn set_all_ports_status() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let china_port = china.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(100));
+        let benin_port1 = benin.add_port(PortID(1), 10, Point2D::default()).unwrap();
+        let benin_port2 = benin.add_port(PortID(2), 10, Point2D::default()).unwrap();
+        let benin_id = benin.id();
+
+        let mut graph = PortGraph::from_edges(vec![china_port, benin_port1, benin_port2], vec![]).unwrap();
+        graph.add_undirected_connection(PortID(0), PortID(1)).unwrap();
+        graph.add_undirected_connection(PortID(1), PortID(2)).unwrap();
+        graph.add_undirected_connection(PortID(2), PortID(0)).unwrap();
+        let mut geography = SimulationGeography::new(graph, vec![china, benin]);
+        assert_eq!(geography.get_open_dest_ports(PortID(0)).unwrap().len(), 2);
+
+        geography.set_all_ports_status(PortStatus::Closed, 5);
+        for port_id in [PortID(0), PortID(1), PortID(2)] {
+            assert!(geography.get_open_dest_ports(port_id).unwrap().is_empty());
+            assert_eq!(geography.get_port(port_id).unwrap().port_status(), PortStatus::Closed);
+        }
+        // region copies of ports also closed
+        for port in geography.get_region(benin_id).unwrap().get_ports() {
+            assert_eq!(port.port_status(), PortStatus::Closed);
+        }
+
+        geography.set_all_ports_status(PortStatus::Open, 6);
+        assert_eq!(geography.get_open_dest_ports(PortID(0)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn apply_port_mixing() {
+        seed_random(6);
+        let mut china = Region::new("China".to_owned(), Population {healthy: 5000, infected: 5000, dead: 0, recovered: 0});
+        let china_port = china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        let mut japan = Region::new("Japan".to_owned(), Population {healthy: 5000, infected: 5000, dead: 0, recovered: 0});
+        let japan_port = japan.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        let benin_port = benin.add_port(PortID(2), 1000, Point2D::default()).unwrap();
+        let mut korea = Region::new("Korea".to_owned(), Population {healthy: 5000, infected: 5000, dead: 0, recovered: 0});
+        let korea_port = korea.add_port(PortID(3), 1000, Point2D::default()).unwrap();
+        let (china_id, japan_id, benin_id, korea_id) = (china.id(), japan.id(), benin.id(), korea.id());
+
+        let graph = PortGraph::from_edges(vec![china_port, japan_port, benin_port, korea_port], vec![]).unwrap();
+        let mut geography = SimulationGeography::new(graph, vec![china, japan, benin, korea]);
+        geography.set_port_status(PortID(3), PortStatus::Closed, 0).unwrap();
+        let before = geography.population_snapshot();
+
+        // china's port is saturated while japan's is barely used
+        let job = |start_port: u32, start_region: RegionID, population: Population| TransportJob {job_id: None, start_port: PortID(start_port), start_region, end_port: PortID(9), end_region: start_region, population, time: 1};
+        let mut jobs = vec![
+            job(0, china_id, Population {healthy: 400, infected: 100, dead: 0, recovered: 0}),
+            job(0, china_id, Population::new_healthy(500)),
+            job(1, japan_id, Population {healthy: 40, infected: 10, dead: 0, recovered: 0}),
+            job(2, benin_id, Population::new_healthy(1000)),
+            job(3, korea_id, Population {healthy: 500, infected: 500, dead: 0, recovered: 0}),
+            job(7, korea_id, Population {healthy: 500, infected: 500, dead: 0, recovered: 0}),
+        ];
+        let original_jobs = jobs.clone();
+        assert!(geography.apply_port_mixing(1.5, &mut jobs).is_err());
+        assert_eq!(jobs, original_jobs);
+
+        geography.apply_port_mixing(1.0, &mut jobs).unwrap();
+        // busy port with infected travellers spreads infection, even to jobs that started out healthy
+        let china_infections = jobs[0].population.infected + jobs[1].population.infected - 100;
+        let japan_infections = jobs[2].population.infected - 10;
+        assert!(jobs[1].population.infected > 0);
+        assert!(japan_infections < china_infections);
+        for (job, original) in jobs.iter().zip(&original_jobs) {
+            assert_eq!(job.population.get_total(), original.population.get_total());
+        }
+        // no infected travellers, closed ports or unknown ports means no spread
+        assert_eq!(jobs[3..], original_jobs[3..]);
+        // people staying behind are not affected
+        assert_eq!(geography.population_snapshot(), before);
+    }
+
+    #[test]
+    fn apply_births() {
+        seed_random(4);
+        let mut china = Region::new("China".to_owned(), Population {healthy: 5000, infected: 0, dead: 1000, recovered: 500});
+        china.birth_rate = 0.01;
+        let benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        let (china_id, benin_id) = (china.id(), benin.id());
+        let mut geography = SimulationGeography::new(PortGraph::new(), vec![china, benin]);
+
+        let mut previous = *geography.get_population(china_id).unwrap();
+        for _ in 0..20 {
+            geography.apply_births().unwrap();
+            let population = *geography.get_population(china_id).unwrap();
+            assert!(population.healthy >= previous.healthy);
+            assert_eq!((population.infected, population.dead, population.recovered), (0, 1000, 500));
+            previous = population;
+        }
+        // roughly 1% growth of the alive population per tick
+        assert!(previous.healthy > 6000 && previous.healthy < 7000, "{:?}", previous);
+        assert_eq!(*geography.get_population(benin_id).unwrap(), Population::new_healthy(5000));
+
+        geography.with_region_mut(benin_id, |region| region.birth_rate = 2.0).unwrap();
+        assert!(geography.apply_births().is_err());
+        assert_eq!(*geography.get_population(china_id).unwrap(), previous);
+    }
+
+    #[test]
+    fn infected_regions() {
+        let china = Region::new("China".to_owned(), Population {healthy: 100, infected: 3, dead: 0, recovered: 0});
+        let benin = Region::new("Benin".to_owned(), Population {healthy: 30, infected: 0, dead: 5, recovered: 5});
+        let atlantis = Region::new("Atlantis".to_owned(), Population {healthy: 0, infected: 1, dead: 0, recovered: 0});
+        let (china_id, benin_id, atlantis_id) = (china.id(), benin.id(), atlantis.id());
+        let mut geography = SimulationGeography::new(PortGraph::new(), vec![benin, atlantis, china]);
+        assert_eq!(geography.infected_regions(), vec![china_id, atlantis_id]);
+
+        geography.infect_region(benin_id, 1).unwrap();
+        geography.with_region_mut(china_id, |region| region.population.infected = 0).unwrap();
+        assert_eq!(geography.infected_regions(), vec![benin_id, atlantis_id]);
+
+        assert!(SimulationGeography::<Population>::new(PortGraph::new(), vec![]).infected_regions().is_empty());
+    }
+
+    #[test]
+    fn get_region_by_name() {
+        let china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let first_georgia = Region::new("Georgia".to_owned(), Population::new_healthy(30));
+        let mut second_georgia = Region::new("Atlantis".to_owned(), Population::new_healthy(5));
+        second_georgia.set_name("Georgia".to_owned());
+        let (china_id, georgia_id) = (china.id(), first_georgia.id());
+        let geography = SimulationGeography::new(PortGraph::new(), vec![second_georgia, china, first_georgia]);
+
+        assert_eq!(geography.get_region_by_name("China").unwrap().id(), china_id);
+        // first region created is found when names are shared
+        assert_eq!(geography.get_region_by_name("Georgia").unwrap().id(), georgia_id);
+        assert!(geography.get_region_by_name("Atlantis").is_none());
+        assert!(geography.get_region_by_name("china").is_none());
+    }
+
+    #[test]
+    fn clone_independent() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let china_port = china.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(3000));
+        let benin_port = benin.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let graph = PortGraph::from_edges(vec![china_port, benin_port], vec![(PortID(0), PortID(1))]).unwrap();
+        let mut original = SimulationGeography::new(graph, vec![china, benin]);
+        original.close_port(PortID(1), 2).unwrap();
+
+        let mut copy = original.clone();
+        assert_eq!(copy.get_port(PortID(1)).unwrap().port_status(), PortStatus::Closed);
+        assert_eq!(copy.population_snapshot(), original.population_snapshot());
+
+        copy.infect_region(china_id, 10).unwrap();
+        copy.close_port(PortID(0), 3).unwrap();
+        copy.set_port_status(PortID(1), PortStatus::Open, 4).unwrap();
+        copy.move_port(PortID(1), Point2D::new(5.0, 5.0)).unwrap();
+
+        assert_eq!(*original.get_population(china_id).unwrap(), Population::new_healthy(5000));
+        for port_id in [PortID(0), PortID(1)] {
+            let expected = if port_id == PortID(0) { PortStatus::Open } else { PortStatus::Closed };
+            assert_eq!(original.get_port(port_id).unwrap().port_status(), expected);
+            assert_eq!(original.get_region(original.get_port(port_id).unwrap().region()).unwrap().get_port(port_id).unwrap().port_status(), expected);
+        }
+        assert_eq!(original.get_port(PortID(1)).unwrap().status_history(), vec![(2, PortStatus::Closed)]);
+        assert_eq!(original.get_port(PortID(1)).unwrap().pos, Point2D::default());
+        assert_eq!(copy.get_port(PortID(0)).unwrap().port_status(), PortStatus::Closed);
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(100));
+        let ports = vec![
+            china.add_port(PortID(2), 100, Point2D::default()).unwrap(),
+            china.add_port(PortID(0), 100, Point2D::default()).unwrap(),
+            china.add_port(PortID(1), 100, Point2D::default()).unwrap(),
+        ];
+        let graph = PortGraph::from_edges(ports, vec![(PortID(0), PortID(1)), (PortID(1), PortID(2)), (PortID(2), PortID(0)), (PortID(0), PortID(2))]).unwrap();
+        let mut geography = SimulationGeography::new(graph, vec![china]);
+        geography.close_port(PortID(1), 0).unwrap();
+
+        let dot = geography.to_dot();
+        assert!(dot.starts_with("digraph ports {\n") && dot.ends_with("}\n"));
+        assert_eq!(dot.lines().filter(|line| line.contains("[label=")).count(), 3);
+        assert_eq!(dot.lines().filter(|line| line.contains(" -> ")).count(), 4);
+        assert!(dot.contains("    1 [label=\"Port 1\\nClosed\"];\n"));
+        assert!(dot.contains("    0 -> 1;\n    0 -> 2;\n"));
+    }
+
+    #[test]
+    fn any_active_infection() {
+        let china = Region::new("China".to_owned(), Population {healthy: 100, infected: 0, dead: 20, recovered: 30});
+        let benin = Region::new("Benin".to_owned(), Population::new_healthy(30));
+        let benin_id = benin.id();
+        let mut geography = SimulationGeography::new(PortGraph::new(), vec![china, benin]);
+        assert!(!geography.any_active_infection());
+
+        geography.infect_region(benin_id, 1).unwrap();
+        assert!(geography.any_active_infection());
+    }
+
+    #[test]
+    fn move_port() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let china_port = china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        benin.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let benin_id = benin.id();
+
+        // port 1 is missing from graph
+        let graph = PortGraph::from_edges(vec![china_port], vec![]).unwrap();
+        let mut geography = SimulationGeography::new(graph, vec![china, benin]);
+
+        geography.move_port(PortID(0), Point2D::new(3.0, 4.0)).unwrap();
+        assert_eq!(geography.get_port(PortID(0)).unwrap().pos, Point2D::new(3.0, 4.0));
+        assert_eq!(geography.get_region(china_id).unwrap().get_port(PortID(0)).unwrap().pos, Point2D::new(3.0, 4.0));
+
+        assert!(geography.move_port(PortID(1), Point2D::new(3.0, 4.0)).is_err());
+        assert_eq!(geography.get_region(benin_id).unwrap().get_port(PortID(1)).unwrap().pos, Point2D::default());
+        assert!(geography.move_port(PortID(7), Point2D::new(3.0, 4.0)).is_err());
+    }
+
+    #[test]
+    fn counts() {
+        let empty: SimulationGeography<Population> = SimulationGeography::new(PortGraph::new(), vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.port_count(), 0);
+        assert_eq!(empty.region_count(), 0);
+
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        let china_port1 = china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        let china_port2 = china.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        let benin_port = benin.add_port(PortID(2), 1000, Point2D::default()).unwrap();
+        let atlantis = Region::new("Atlantis".to_owned(), Population::new_healthy(5000));
+
+        let graph = PortGraph::from_edges(vec![china_port1, china_port2, benin_port], vec![(PortID(0), PortID(2))]).unwrap();
+        let geography = SimulationGeography::new(graph, vec![china, benin, atlantis]);
+        assert!(!geography.is_empty());
+        assert_eq!(geography.port_count(), 3);
+        assert_eq!(geography.region_count(), 3);
+    }
+
+    #[test]
+    fn from_regions() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        china.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        benin.add_port(PortID(2), 1000, Point2D::default()).unwrap();
+        let benin_id = benin.id();
+
+        let geography = SimulationGeography::from_regions(vec![china.clone(), benin.clone()], vec![(PortID(0), PortID(2)), (PortID(2), PortID(1))]).unwrap();
+        assert_eq!(geography.port_count(), 3);
+        let dests = geography.get_open_dest_ports(PortID(0)).unwrap();
+        assert_eq!(dests.len(), 1);
+        assert_eq!(dests[0].id, PortID(2));
+        assert_eq!(dests[0].region(), benin_id);
+        assert_eq!(geography.get_open_dest_ports(PortID(2)).unwrap()[0].id, PortID(1));
+        assert!(geography.get_open_dest_ports(PortID(1)).unwrap().is_empty());
+
+        // edges must refer to ports of regions
+        assert!(SimulationGeography::from_regions(vec![china.clone(), benin.clone()], vec![(PortID(0), PortID(5))]).is_err());
+        // port IDs must be unique
+        let mut duplicate = Region::new("Atlantis".to_owned(), Population::new_healthy(5000));
+        duplicate.add_port(PortID(2), 10, Point2D::default()).unwrap();
+        assert!(SimulationGeography::from_regions(vec![china, benin, duplicate], vec![]).is_err());
+    }
+
+    #[test]
+    fn with_port_mut() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        benin.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let mut geography = SimulationGeography::from_regions(vec![china, benin], vec![(PortID(0), PortID(1))]).unwrap();
+
+        geography.with_port_mut(PortID(0), |port| port.capacity = 42).unwrap();
+        assert_eq!(geography.get_port(PortID(0)).unwrap().capacity, 42);
+        assert_eq!(geography.get_region(china_id).unwrap().get_port(PortID(0)).unwrap().capacity, 42);
+        assert_eq!(geography.get_port(PortID(0)), geography.get_region(china_id).unwrap().get_port(PortID(0)));
+
+        // IDs cannot be changed
+        assert!(geography.with_port_mut(PortID(0), |port| port.id = PortID(5)).is_err());
+        assert!(geography.get_region(china_id).unwrap().get_port(PortID(0)).is_some());
+        assert!(geography.get_port(PortID(0)).is_some());
+        assert!(geography.with_port_mut(PortID(7), |port| port.capacity = 1).is_err());
+
+        // statuses cannot be changed either, but other changes are kept
+        assert!(geography.with_port_mut(PortID(0), |port| {
+            port.close_port(3);
+            port.capacity = 7;
+        }).is_err());
+        for port in [geography.get_port(PortID(0)).unwrap(), geography.get_region(china_id).unwrap().get_port(PortID(0)).unwrap()] {
+            assert_eq!(port.port_status(), PortStatus::Open);
+            assert!(port.status_history().is_empty());
+            assert_eq!(port.region(), china_id);
+            assert_eq!(port.capacity, 7);
+        }
+    }
+
+    #[test]
+    fn restore_port_statuses() {
+        let mut china = Region::new("China".to_owned(), Population::new_healthy(5000));
+        china.add_port(PortID(0), 1000, Point2D::default()).unwrap();
+        let china_id = china.id();
+        let mut benin = Region::new("Benin".to_owned(), Population::new_healthy(5000));
+        benin.add_port(PortID(1), 1000, Point2D::default()).unwrap();
+        let mut geography = SimulationGeography::from_regions(vec![china, benin], vec![(PortID(0), PortID(1))]).unwrap();
+        geography.set_port_status(PortID(1), PortStatus::Closed, 1).unwrap();
+        let original_ports: Vec<Port> = geography.get_ports().into_iter().cloned().collect();
+
+        geography.set_port_status(PortID(0), PortStatus::Closed, 4).unwrap();
+        geography.set_port_status(PortID(1), PortStatus::Open, 5).unwrap();
+        geography.restore_port_statuses(&original_ports).unwrap();
+        for port in [geography.get_port(PortID(0)).unwrap(), geography.get_region(china_id).unwrap().get_port(PortID(0)).unwrap()] {
+            assert_eq!(port.port_status(), PortStatus::Open);
+            assert!(port.status_history().is_empty());
+        }
+        assert_eq!(geography.get_port(PortID(1)).unwrap().status_history(), vec![(1, PortStatus::Closed)]);
+
+        // nothing is restored if any port is missing
+        let mut stranger = Region::new("Atlantis".to_owned(), Population::new_healthy(10));
+        let missing_port = stranger.add_port(PortID(9), 10, Point2D::default()).unwrap();
+        geography.set_port_status(PortID(0), PortStatus::Closed, 6).unwrap();
+        assert!(geography.restore_port_statuses(&[original_ports[0].clone(), missing_port]).is_err());
+        assert_eq!(geography.get_port(PortID(0)).unwrap().port_status(), PortStatus::Closed);
+    }
+
+    #[test]
+    fn duplicate_port_ids_across_regions() {
+        let mut first = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let mut second = Region::new("Lemuria".to_owned(), Population::new_healthy(100));
+        first.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        second.add_port(PortID(1), 10, Point2D::default()).unwrap();
+        assert!(SimulationGeography::check_unique_port_ids(&[first.clone(), second.clone()]).is_ok());
+
+        second.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        let error = SimulationGeography::check_unique_port_ids(&[first.clone(), second.clone()]).unwrap_err();
+        assert!(error.contains("Port ID 0"));
+        assert!(SimulationGeography::from_regions(vec![first.clone(), second.clone()], vec![]).is_err());
+
+        // new only checks in debug builds
+        #[cfg(debug_assertions)]
+        {
+            let result = std::panic::catch_unwind(|| SimulationGeography::new(PortGraph::new(), vec![first, second]));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn split_region() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let port1 = country.add_port(PortID(0), 10, Point2D::default()).unwrap();
+        let port2 = country.add_port(PortID(1), 10, Point2D::default()).unwrap();
+        let country_id = country.id();
+        let mut geography = SimulationGeography::from_regions(vec![country], vec![(port1.id, port2.id)]).unwrap();
+        assert!(geography.connected_regions(country_id).is_empty());
+
+        assert!(geography.split_region(country_id, 2.0, "Lemuria".to_owned(), &[PortID(1)]).is_err());
+        assert!(geography.split_region(RegionID::from(u32::MAX), 0.5, "Lemuria".to_owned(), &[PortID(1)]).is_err());
+        assert_eq!(geography.region_count(), 1);
+
+        let new_id = geography.split_region(country_id, 0.25, "Lemuria".to_owned(), &[PortID(1)]).unwrap();
+        assert_eq!(geography.region_count(), 2);
+        assert_eq!(geography.get_population(new_id).unwrap().get_total(), 25);
+        assert_eq!(geography.get_population(country_id).unwrap().get_total(), 75);
+        assert_eq!(geography.get_port(PortID(1)).unwrap().region(), new_id);
+        assert_eq!(geography.get_port(PortID(0)).unwrap().region(), country_id);
+        assert_eq!(geography.connected_regions(country_id), HashSet::from([new_id]));
+    }
+
+    #[test]
+    fn duplicate_region_ids() {
+        let first = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let second = Region::new("Lemuria".to_owned(), Population::new_healthy(100));
+        assert!(SimulationGeography::check_unique_region_ids(&[first.clone(), second.clone()]).is_ok());
+
+        let error = SimulationGeography::check_unique_region_ids(&[first.clone(), second.clone(), first.clone()]).unwrap_err();
+        assert!(error.contains(&format!("Region ID {}", first.id())));
+        assert!(SimulationGeography::from_regions(vec![first.clone(), second, first], vec![]).is_err());
+    }
+
+    #[test]
+    fn advance_populations() {
+        let recovering = Region::new("Atlantis".to_owned(), TemporaryImmunityPopulation::new(Population {healthy: 10, infected: 0, dead: 0, recovered: 5}, 2).unwrap());
+        let healthy = Region::new("Lemuria".to_owned(), TemporaryImmunityPopulation::new(Population::new_healthy(10), 2).unwrap());
+        let recovering_id = recovering.id();
+        let mut geography = SimulationGeography::from_regions(vec![recovering, healthy], vec![]).unwrap();
+
+        assert!(geography.advance_populations().is_empty());
+        let changes = geography.advance_populations();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[&recovering_id], (Population {healthy: 10, infected: 0, dead: 0, recovered: 5}, Population::new_healthy(15)));
+        assert_eq!(geography.get_population(recovering_id).unwrap().population(), Population::new_healthy(15));
+    }
+}
@@ -1,6 +1,6 @@
 // Responsible for calculating ways to allocate people to transportation
 
-use crate::{point::{Point2D}, math_utils::{get_random, pick_random}, population_types::{population::Population, PopulationType}, region::{Port, PortID, Region, RegionID}};
+use crate::{point::{Point2D}, math_utils::{binomial_sample, get_random, pick_random, random_u32_in}, population_types::{population::Population, PopulationType}, region::{Port, PortID, Region, RegionID}, simulation_geography::SimulationGeography, travel_cost::{EuclideanCost, TravelCost}};
 
 
 
@@ -11,44 +11,158 @@ use crate::{point::{Point2D}, math_utils::{get_random, pick_random}, population_
 /** - Use None to communicate that no jobs could be created, e.g. region is uninhabited */
 pub trait TransportAllocator<P = Population> where P: PopulationType {
     fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region<P>, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>>;
+
+    /// Called once per tick before any calls to calculate_transport, so stateful allocators can look at the whole geography first
+    /// 
+    /// Does nothing by default
+    fn begin_tick(&mut self, _geography: &SimulationGeography<P>) {}
+
+    /// Called once per region after calculate_transport has been called for each of its ports, with every job this allocator created for the region, so jobs can be adjusted together
+    /// 
+    /// Jobs may be changed or removed, but must still be possible to extract from the start region
+    /// 
+    /// Does nothing by default
+    fn end_region(&self, _start_region: &Region<P>, _jobs: &mut Vec<TransportJob>) {}
+
+    /// Called when the simulation using this allocator starts over, so stateful allocators can forget what they have seen
+    /// 
+    /// Does nothing by default
+    fn reset(&mut self) {}
 }
 
 /// Randomly choose a port to travel to, and transport a random number of people up to the starting port's capacity
 /// transport_probability refers to chance that a transport will occur, if possible
 /** Population transported reflects composition of starting region
  * For example, this allocator will have a transport consisting of roughly 50% infected if starting region is also 50% infected */
-pub struct RandomTransportAllocator {
-    pub transport_probability: f32
+/// infected_travel_factor scales how likely infected people are to travel compared to everyone else, see set_infected_travel_factor
+/// * 1.0 means infected people travel as often as everyone else
+/// * 0.0 means infected people never travel
+/// * Must be finite and not negative
+/// 
+/// include_dead determines whether dead people are transported, defaults to false
+/// * When false, dead people's share of a transport goes to the living instead
+/// 
+/// travel_cost determines how long transports take
+/// 
+/// arrival_spread spreads out arrivals of a transport, defaults to 0
+/// * When 0, everyone transported arrives together
+/// * Otherwise, people transported are split into several jobs, each taking a random time up to arrival_spread ticks more or less than travel_cost determines
+/// * The window of times is narrowed to stay centred on travel_cost's time when that time is shorter than arrival_spread
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomTransportAllocator<C = EuclideanCost> where C: TravelCost {
+    pub transport_probability: f32,
+    infected_travel_factor: f64,
+    pub include_dead: bool,
+    pub arrival_spread: u32,
+    pub travel_cost: C
 }
 
 impl RandomTransportAllocator {
+    /// Creates allocator whose infected people travel as often as everyone else, and which never transports dead people
+    /// 
+    /// Transports take as long as the distance between ports
     pub fn new(transport_probability: f32) -> Self {
-        Self {transport_probability}
+        Self::with_travel_cost(transport_probability, EuclideanCost)
+    }
+}
+
+impl Default for RandomTransportAllocator {
+    /// Creates allocator with a 1% chance of transporting, see new
+    fn default() -> Self {
+        Self::new(0.01)
     }
 }
 
-impl<P: PopulationType> TransportAllocator <P> for RandomTransportAllocator {
+impl<C> RandomTransportAllocator<C> where C: TravelCost {
+    /// Creates allocator whose infected people travel as often as everyone else, and which never transports dead people
+    /// 
+    /// Transports take as long as travel_cost determines
+    pub fn with_travel_cost(transport_probability: f32, travel_cost: C) -> Self {
+        Self {transport_probability, infected_travel_factor: 1.0, include_dead: false, arrival_spread: 0, travel_cost}
+    }
+
+    /// Gets how likely infected people are to travel compared to everyone else
+    pub fn infected_travel_factor(&self) -> f64 {
+        self.infected_travel_factor
+    }
+
+    /// Sets how likely infected people are to travel compared to everyone else
+    /// # Errors
+    /// * Fails if factor is negative or not finite, leaving factor unchanged
+    pub fn set_infected_travel_factor(&mut self, factor: f64) -> Result<(), String> {
+        if !(factor.is_finite() && factor >= 0.0_f64) {
+            return Err(format!("Infected travel factor must be finite and not negative, not {factor}"));
+        }
+        self.infected_travel_factor = factor;
+        Ok(())
+    }
+
+    /// Picks roughly the requested amount of people from population, weighting infected people by infected_travel_factor
+    /// 
+    /// Only picks dead people if include_dead is set
+    /// 
+    /// Never picks more people of any group than exists in the population
+    fn pick_travelers(&self, population: Population, amount: u32) -> Population {
+        let weighted_infected = population.infected as f64*self.infected_travel_factor;
+        let weighted_dead = if self.include_dead { population.dead as f64 } else { 0.0_f64 };
+        let weighted_total = population.healthy as f64 + weighted_dead + population.recovered as f64 + weighted_infected;
+        if weighted_total <= 0.0_f64 {
+            return Population::default();
+        }
+        let scale_factor = (amount as f64)/weighted_total;
+        let pick = |group: u32, weight: f64| ((weight*scale_factor).round() as u32).min(group);
+        Population {
+            healthy: pick(population.healthy, population.healthy as f64),
+            infected: pick(population.infected, weighted_infected),
+            dead: pick(population.dead, weighted_dead),
+            recovered: pick(population.recovered, population.recovered as f64)
+        }
+    }
+
+    /// Splits travelers into groups arriving at random times around mean_time, according to arrival_spread
+    /// 
+    /// Times are picked from a window centred on mean_time, narrowed if needed so that no time is negative
+    /// 
+    /// Returns each group with its travel time, never returning empty groups
+    fn spread_arrivals(&self, travelers: Population, mean_time: u32) -> Vec<(Population, u32)> {
+        if self.arrival_spread == 0 {
+            return vec![(travelers, mean_time)];
+        }
+        let earliest = mean_time.saturating_sub(self.arrival_spread);
+        let latest = mean_time.saturating_add(mean_time.saturating_sub(earliest));
+        let group_count = self.arrival_spread.saturating_mul(2).saturating_add(1).min(travelers.get_total()).max(1);
+        let group_size = travelers.get_total().checked_div(group_count).unwrap_or_default();
+        let mut remaining = travelers;
+        let mut groups = vec![];
+        for group in 0..group_count {
+            // last group takes everyone left over
+            let group_population = if group == group_count.saturating_sub(1) {
+                remaining
+            } else {
+                remaining.partition_random(group_size).unwrap_or_default()
+            };
+            remaining = remaining.emigrate(group_population).unwrap_or_default();
+            if group_population.get_total() > 0 {
+                groups.push((group_population, random_u32_in(earliest, latest)));
+            }
+        }
+        groups
+    }
+}
+
+impl<P: PopulationType, C: TravelCost> TransportAllocator <P> for RandomTransportAllocator<C> {
     fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region<P>, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
         // only prepare a transport if random chance favors it
         if (get_random() as f32) < self.transport_probability {
             let random_dest = pick_random(destination_port_choices);
             match random_dest {
                 Some(dest) => {
-                    let random_pop = ((start_port.capacity + 1) as f64*get_random()) as u32;
+                    let random_pop = start_port.sample_count();
                     // do not transport if empty
                     if random_pop == 0 {
                         return None;
                     }
-                    let transported_population;
-                    // transport entire population
-                    if random_pop >= start_region.population.population().get_total() {
-                        transported_population = start_region.population.population();
-                    } 
-                    // transport only portion
-                    else {
-                        let scale_factor = (random_pop as f64)/(start_region.population.population().get_total() as f64);
-                        transported_population = start_region.population.population().scale(scale_factor);
-                    }
+                    let transported_population = self.pick_travelers(start_region.population.population(), random_pop);
                     debug_assert!(transported_population.healthy <= start_region.population.population().healthy, "{}", 
                     format!("Unable to remove {} healthy from {} healthy", transported_population.healthy, start_region.population.population().healthy));
                     debug_assert!(transported_population.dead <= start_region.population.population().dead, "{}", 
@@ -57,9 +171,14 @@ impl<P: PopulationType> TransportAllocator <P> for RandomTransportAllocator {
                     format!("Unable to remove {} infected from {} infected", transported_population.infected, start_region.population.population().infected));
                     debug_assert!(transported_population.recovered <= start_region.population.population().recovered, "{}", 
                     format!("Unable to remove {} recovered from {} recovered", transported_population.recovered, start_region.population.population().recovered));
-                    // TODO! Change time calculation later to allow changes in speed
-                    let distance = start_port.pos.distance(&dest.pos) as u32;
-                    Some(vec![TransportJob {start_region: start_region.id(), start_port: start_port.id, end_region: dest.region(), end_port: dest.id, population: transported_population, time: distance}])
+                    // do not transport if travel time cannot be calculated
+                    let time = match self.travel_cost.ticks(start_port, dest) {
+                        Ok(time) => time,
+                        Err(_) => return None,
+                    };
+                    Some(self.spread_arrivals(transported_population, time).into_iter()
+                        .map(|(population, time)| TransportJob {job_id: None, start_region: start_region.id(), start_port: start_port.id, end_region: dest.region(), end_port: dest.id, population, time})
+                        .collect())
                 },
                 None => None,
             }
@@ -69,7 +188,172 @@ impl<P: PopulationType> TransportAllocator <P> for RandomTransportAllocator {
     }
 }
 
+/// Dispatches each destination to one of two allocators
+/// 
+/// is_first receives the ids of the start and destination regions, and decides whether first (true) or second (false) handles the destination
+/// 
+/// For example, first could handle domestic travel while second handles international travel
+/// 
+/// Both allocators see the entire start region, so second's jobs are dropped if they cannot be extracted alongside first's jobs
+pub struct RoutedAllocator<A, B, F> where F: Fn(RegionID, RegionID) -> bool {
+    pub first: A,
+    pub second: B,
+    pub is_first: F
+}
+
+impl<A, B, F> RoutedAllocator<A, B, F> where F: Fn(RegionID, RegionID) -> bool {
+    pub fn new(first: A, second: B, is_first: F) -> Self {
+        Self {first, second, is_first}
+    }
+}
+
+impl<P: PopulationType, A: TransportAllocator<P>, B: TransportAllocator<P>, F: Fn(RegionID, RegionID) -> bool> TransportAllocator<P> for RoutedAllocator<A, B, F> {
+    fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region<P>, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
+        let (first_dests, second_dests): (Vec<&Port>, Vec<&Port>) = destination_port_choices.into_iter()
+            .partition(|dest| (self.is_first)(start_region.id(), dest.region()));
+        let mut jobs = Vec::new();
+        if !first_dests.is_empty() {
+            jobs.extend(self.first.calculate_transport(start_port, start_region, first_dests).unwrap_or_default());
+        }
+        if !second_dests.is_empty() {
+            let second_jobs = self.second.calculate_transport(start_port, start_region, second_dests).unwrap_or_default();
+            // only keep second's jobs if the start region can supply everyone
+            let all_transported: Population = jobs.iter().chain(second_jobs.iter()).map(|job| job.population).sum();
+            if start_region.population.population().emigrate(all_transported).is_ok() {
+                jobs.extend(second_jobs);
+            }
+        }
+        if jobs.is_empty() {
+            None
+        } else {
+            Some(jobs)
+        }
+    }
+
+    fn begin_tick(&mut self, geography: &SimulationGeography<P>) {
+        self.first.begin_tick(geography);
+        self.second.begin_tick(geography);
+    }
+
+    fn end_region(&self, start_region: &Region<P>, jobs: &mut Vec<TransportJob>) {
+        let (mut first_jobs, mut second_jobs): (Vec<TransportJob>, Vec<TransportJob>) = jobs.drain(..)
+            .partition(|job| (self.is_first)(start_region.id(), job.end_region));
+        self.first.end_region(start_region, &mut first_jobs);
+        self.second.end_region(start_region, &mut second_jobs);
+        jobs.extend(first_jobs);
+        jobs.extend(second_jobs);
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+/// Screens travelers leaving ports with screening, keeping detected infected people from travelling
+/// 
+/// detection_rate is the chance of each infected traveler being detected, and must be between 0 and 1
+/// 
+/// Transports from ports without screening are left unchanged
+pub struct ScreeningTransportAllocator<A> {
+    pub inner: A,
+    pub detection_rate: f64
+}
+
+impl<A> ScreeningTransportAllocator<A> {
+    /// Fails if detection rate is not between 0 and 1
+    pub fn new(inner: A, detection_rate: f64) -> Result<Self, String> {
+        if !(0.0_f64..=1.0_f64).contains(&detection_rate) {
+            return Err(format!("Detection rate must be between 0 and 1, not {detection_rate}"));
+        }
+        Ok(Self {inner, detection_rate})
+    }
+}
+
+impl<P: PopulationType, A: TransportAllocator<P>> TransportAllocator<P> for ScreeningTransportAllocator<A> {
+    fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region<P>, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
+        let mut jobs = self.inner.calculate_transport(start_port, start_region, destination_port_choices)?;
+        if start_port.has_screening {
+            for job in &mut jobs {
+                // detected people stay in start region
+                let detected = binomial_sample(job.population.infected, self.detection_rate);
+                job.population.infected = job.population.infected.saturating_sub(detected);
+            }
+            jobs.retain(|job| job.population.get_total() > 0);
+        }
+        if jobs.is_empty() {
+            None
+        } else {
+            Some(jobs)
+        }
+    }
+
+    fn begin_tick(&mut self, geography: &SimulationGeography<P>) {
+        self.inner.begin_tick(geography);
+    }
+
+    fn end_region(&self, start_region: &Region<P>, jobs: &mut Vec<TransportJob>) {
+        self.inner.end_region(start_region, jobs);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Caps how many people may leave a region each tick, regardless of port capacities
+/// 
+/// max_fraction is the largest fraction of the start region's living population that may depart, and must be between 0 and 1
+/// 
+/// The cap is applied in end_region, once every port of a region has been allocated
+/// * If the region's jobs together exceed the cap, every job is scaled down by the same factor, rounding down
+/// * Jobs left empty are removed
+pub struct DepartureCapAllocator<A> {
+    pub inner: A,
+    pub max_fraction: f64
+}
+
+impl<A> DepartureCapAllocator<A> {
+    /// Fails if max fraction is not between 0 and 1
+    pub fn new(inner: A, max_fraction: f64) -> Result<Self, String> {
+        if !(0.0_f64..=1.0_f64).contains(&max_fraction) {
+            return Err(format!("Max fraction must be between 0 and 1, not {max_fraction}"));
+        }
+        Ok(Self {inner, max_fraction})
+    }
+}
+
+impl<P: PopulationType, A: TransportAllocator<P>> TransportAllocator<P> for DepartureCapAllocator<A> {
+    fn calculate_transport<'a>(&self, start_port: &Port, start_region: &Region<P>, destination_port_choices: Vec<&Port>) -> Option<Vec<TransportJob>> {
+        self.inner.calculate_transport(start_port, start_region, destination_port_choices)
+    }
+
+    fn begin_tick(&mut self, geography: &SimulationGeography<P>) {
+        self.inner.begin_tick(geography);
+    }
+
+    fn end_region(&self, start_region: &Region<P>, jobs: &mut Vec<TransportJob>) {
+        self.inner.end_region(start_region, jobs);
+        let region_cap = self.max_fraction * f64::from(start_region.population.population().get_alive());
+        let departing = jobs.iter().fold(0_u64, |total, job| total.saturating_add(job.population.get_total_u64())) as f64;
+        if departing > region_cap {
+            let factor = region_cap / departing;
+            for job in jobs.iter_mut() {
+                job.population = job.population.scale_truncate(factor);
+            }
+            jobs.retain(|job| job.population.get_total() > 0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TransportJob {
+    /// Identifies job for tracing purposes, assigned by whoever schedules the job
+    pub job_id: Option<u64>,
     pub start_port: PortID,
     pub start_region: RegionID,
     pub end_port: PortID,
@@ -80,19 +364,21 @@ pub struct TransportJob {
 
 #[cfg(test)]
 mod test {
-    use crate::{point::Point2D, population_types::population::Population, region::{PortID, Region}};
+    use crate::{math_utils::seed_random, point::Point2D, population_types::population::Population, region::{PortID, Region}};
 
-    use super::{RandomTransportAllocator, TransportAllocator};
+    use crate::travel_cost::FlatCost;
+
+    use super::{DepartureCapAllocator, RandomTransportAllocator, RoutedAllocator, ScreeningTransportAllocator, TransportAllocator, TransportJob};
 
     /** This test may pass or fail by random chance */
     #[test]
     fn random_transport_allocator() {
         let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
         brazil.population = Population::new_random(50000);
-        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
 
         let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
-        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(10.0, 2.0));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(10.0, 2.0)).unwrap();
         benin.population = Population::new_random(30000);
 
         let random_alloc = RandomTransportAllocator::new(1.0);
@@ -111,4 +397,271 @@ mod test {
 
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn random_transport_allocator_nan_distance() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let mut benin_port = benin.add_port(PortID(1), 500, Point2D::default()).unwrap();
+        benin_port.pos = Point2D::new(f64::NAN, 2.0);
+
+        assert!(braz_port.pos.try_distance(&benin_port.pos).is_err());
+
+        // malformed destination should never produce an instant arrival
+        let random_alloc = RandomTransportAllocator::new(1.0);
+        for _ in 0..=30 {
+            let jobs = random_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]);
+            assert!(jobs.is_none());
+        }
+    }
+
+    #[test]
+    fn random_transport_allocator_infected_travel_factor() {
+        let population = Population {healthy: 1500, infected: 750, dead: 300, recovered: 20};
+
+        // infected people travel as often as everyone else by default
+        let mut random_alloc = RandomTransportAllocator::new(1.0);
+        random_alloc.include_dead = true;
+        assert_eq!(random_alloc.infected_travel_factor(), 1.0);
+        for factor in [-0.5, f64::NAN, f64::INFINITY] {
+            assert!(random_alloc.set_infected_travel_factor(factor).is_err());
+        }
+        assert_eq!(random_alloc.infected_travel_factor(), 1.0);
+        for amount in [0, 1, 17, 500, 2569] {
+            let scale_factor = (amount as f64)/(population.get_total() as f64);
+            assert_eq!(random_alloc.pick_travelers(population, amount), population.scale(scale_factor));
+        }
+        // cannot pick more than entire population
+        assert_eq!(random_alloc.pick_travelers(population, 10000), population);
+
+        // infected people never travel
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
+        brazil.population = population;
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(10.0, 2.0)).unwrap();
+
+        let mut healthy_alloc = RandomTransportAllocator::new(1.0);
+        healthy_alloc.set_infected_travel_factor(0.0).unwrap();
+        for _ in 0..=30 {
+            let jobs = healthy_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]);
+            for job in jobs.unwrap_or_default() {
+                assert_eq!(job.population.infected, 0);
+                assert!(brazil.population.emigrate(job.population).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn random_transport_allocator_travel_cost() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(30.0, 40.0)).unwrap();
+
+        let distance_alloc = RandomTransportAllocator::new(1.0);
+        let flat_alloc = RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(3));
+        for _ in 0..=30 {
+            for job in distance_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]).unwrap_or_default() {
+                assert_eq!(job.time, 50);
+            }
+            for job in flat_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]).unwrap_or_default() {
+                assert_eq!(job.time, 3);
+            }
+        }
+    }
+
+    #[test]
+    fn routed_allocator() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let braz_port_2 = brazil.add_port(PortID(1), 500, Point2D::new(5.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(2), 500, Point2D::new(30.0, 40.0)).unwrap();
+
+        // domestic transports take 1 tick, international ones take 7
+        let routed_alloc = RoutedAllocator::new(
+            RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(1)), 
+            RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(7)), 
+            |start, end| start == end);
+        for _ in 0..=30 {
+            let jobs = routed_alloc.calculate_transport(&braz_port, &brazil, vec![&braz_port_2, &benin_port]).unwrap_or_default();
+            assert!(jobs.len() <= 2);
+            for job in &jobs {
+                if job.end_port == braz_port_2.id {
+                    assert_eq!(job.time, 1);
+                } else {
+                    assert_eq!(job.end_port, benin_port.id);
+                    assert_eq!(job.time, 7);
+                }
+            }
+            let total: Population = jobs.iter().map(|job| job.population).sum();
+            assert!(brazil.population.emigrate(total).is_ok());
+        }
+    }
+
+    #[test]
+    fn random_transport_allocator_excludes_dead() {
+        let population = Population {healthy: 1500, infected: 750, dead: 3000, recovered: 20};
+        let mut brazil: Region = Region::new("Brazil".to_owned(), population);
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(10.0, 2.0)).unwrap();
+
+        // dead people's share goes to the living
+        let random_alloc = RandomTransportAllocator::new(1.0);
+        let travelers = random_alloc.pick_travelers(population, 454);
+        assert_eq!(travelers.dead, 0);
+        assert_eq!(travelers.get_total(), 454);
+
+        for _ in 0..=30 {
+            for job in random_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]).unwrap_or_default() {
+                assert_eq!(job.population.dead, 0);
+                assert!(brazil.population.emigrate(job.population).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn random_transport_allocator_arrival_spread() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_random(50000));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(30.0, 40.0)).unwrap();
+
+        let mut spread_alloc = RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(10));
+        spread_alloc.arrival_spread = 3;
+        let travelers = Population {healthy: 300, infected: 20, dead: 0, recovered: 100};
+        let groups = spread_alloc.spread_arrivals(travelers, 10);
+        assert_eq!(groups.len(), 7);
+        assert_eq!(groups.iter().map(|(population, _)| population).sum::<Population>(), travelers);
+
+        let mut spread_out = false;
+        for _ in 0..=30 {
+            let jobs = spread_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]).unwrap_or_default();
+            for job in &jobs {
+                assert!((7..=13).contains(&job.time));
+                assert_eq!(job.end_port, benin_port.id);
+            }
+            let total: Population = jobs.iter().map(|job| job.population).sum();
+            assert!(brazil.population.emigrate(total).is_ok());
+            spread_out |= jobs.iter().any(|job| job.time != jobs[0].time);
+        }
+        assert!(spread_out);
+
+        // nobody arrives before leaving, and arrivals stay centred on a travel time shorter than the spread
+        assert!(spread_alloc.spread_arrivals(travelers, 1).iter().all(|(_, time)| *time <= 2));
+        seed_random(9);
+        spread_alloc.arrival_spread = 5;
+        let (mut weighted_time, mut total) = (0_u64, 0_u64);
+        for _ in 0..200 {
+            for (population, time) in spread_alloc.spread_arrivals(travelers, 2) {
+                assert!(time <= 4);
+                weighted_time += u64::from(population.get_total())*u64::from(time);
+                total += u64::from(population.get_total());
+            }
+        }
+        let mean_time = weighted_time as f64/total as f64;
+        assert!((mean_time - 2.0).abs() < 0.2, "{mean_time}");
+        // no spread by default
+        let flat_alloc = RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(10));
+        assert_eq!(flat_alloc.spread_arrivals(travelers, 10), vec![(travelers, 10)]);
+    }
+
+    #[test]
+    fn random_transport_allocator_default() {
+        let default_alloc: RandomTransportAllocator = Default::default();
+        assert_eq!(default_alloc, RandomTransportAllocator::new(0.01));
+    }
+
+    #[test]
+    fn screening_transport_allocator() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population {healthy: 20000, infected: 20000, dead: 0, recovered: 0});
+        brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        brazil.add_port(PortID(1), 500, Point2D::new(0.0, 0.0)).unwrap();
+        brazil.set_port_screening(PortID(1), true).unwrap();
+        assert!(brazil.set_port_screening(PortID(7), true).is_err());
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(2), 500, Point2D::new(10.0, 2.0)).unwrap();
+
+        assert!(ScreeningTransportAllocator::new(RandomTransportAllocator::new(1.0), 1.5).is_err());
+        let screening_alloc = ScreeningTransportAllocator::new(RandomTransportAllocator::new(1.0), 1.0).unwrap();
+        let unscreened_port = brazil.get_port(PortID(0)).unwrap();
+        let screened_port = brazil.get_port(PortID(1)).unwrap();
+        let (mut unscreened_infected, mut screened_infected) = (0, 0);
+        for _ in 0..=30 {
+            for job in screening_alloc.calculate_transport(unscreened_port, &brazil, vec![&benin_port]).unwrap_or_default() {
+                unscreened_infected += job.population.infected;
+            }
+            for job in screening_alloc.calculate_transport(screened_port, &brazil, vec![&benin_port]).unwrap_or_default() {
+                assert!(job.population.healthy > 0);
+                screened_infected += job.population.infected;
+            }
+        }
+        assert!(unscreened_infected > 0);
+        assert_eq!(screened_infected, 0);
+    }
+
+    #[test]
+    fn departure_cap_allocator() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population {healthy: 9000, infected: 1000, dead: 5000, recovered: 0});
+        brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        brazil.add_port(PortID(1), 1500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(2), 500, Point2D::new(10.0, 2.0)).unwrap();
+
+        assert!(DepartureCapAllocator::new(RandomTransportAllocator::new(1.0), -0.1).is_err());
+        // region may lose at most 500 of its 10000 living people per tick, far less than its ports could send
+        let capped_alloc = DepartureCapAllocator::new(RandomTransportAllocator::new(1.0), 0.05).unwrap();
+        let mut max_departures = 0;
+        for _ in 0..=50 {
+            let mut jobs: Vec<TransportJob> = brazil.get_ports().iter()
+                .flat_map(|port| capped_alloc.calculate_transport(port, &brazil, vec![&benin_port]).unwrap_or_default())
+                .collect();
+            let uncapped: Vec<u32> = jobs.iter().map(|job| job.population.get_total()).collect();
+            capped_alloc.end_region(&brazil, &mut jobs);
+            let departures: u32 = jobs.iter().map(|job| job.population.get_total()).sum();
+            assert!(departures <= 500);
+            max_departures = max_departures.max(departures);
+            // every port's jobs are scaled by the same factor, so the busier port keeps sending more
+            if jobs.len() == 2 && uncapped[0] < uncapped[1] {
+                assert!(jobs[0].population.get_total() <= jobs[1].population.get_total());
+            }
+        }
+        assert!(max_departures > 450);
+
+        // jobs within the cap are left alone
+        let mut small_jobs = vec![TransportJob {job_id: None, start_port: PortID(0), start_region: brazil.id(), end_port: PortID(2), end_region: benin.id(), population: Population::new_healthy(100), time: 1}];
+        let original_jobs = small_jobs.clone();
+        capped_alloc.end_region(&brazil, &mut small_jobs);
+        assert_eq!(small_jobs, original_jobs);
+
+        // nobody leaves with a cap of 0
+        let closed_alloc = DepartureCapAllocator::new(RandomTransportAllocator::new(1.0), 0.0).unwrap();
+        closed_alloc.end_region(&brazil, &mut small_jobs);
+        assert!(small_jobs.is_empty());
+    }
+
+    #[test]
+    fn random_transport_allocator_expected_job() {
+        let mut brazil: Region = Region::new("Brazil".to_owned(), Population::new_healthy(50000));
+        let braz_port = brazil.add_port(PortID(0), 500, Point2D::new(0.0, 0.0)).unwrap();
+        let mut benin: Region = Region::new("Benin".to_owned(), Population::new_healthy(30000));
+        let benin_port = benin.add_port(PortID(1), 500, Point2D::new(10.0, 2.0)).unwrap();
+        let random_alloc = RandomTransportAllocator::with_travel_cost(1.0, FlatCost::new(3));
+
+        seed_random(9);
+        let jobs = random_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]).unwrap();
+        // only the amount of people is random, and everyone is healthy
+        let amount = jobs[0].population.healthy;
+        assert!((1..=500).contains(&amount));
+        let expected = TransportJob {job_id: None, start_port: PortID(0), start_region: brazil.id(), end_port: PortID(1), end_region: benin.id(), population: Population::new_healthy(amount), time: 3};
+        assert_eq!(jobs, vec![expected.clone()]);
+
+        // same seed gives the same job
+        seed_random(9);
+        assert_eq!(random_alloc.calculate_transport(&braz_port, &brazil, vec![&benin_port]), Some(vec![expected]));
+    }
+}
@@ -1,14 +1,15 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::{point::Point2D, region::{Port, PortID, PortStatus}};
 
 
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct PortNode {
     port: Port,
     dests: Vec<PortID>
@@ -21,9 +22,20 @@ impl PortNode {
 }
 
 /** Represents a graph of port connections */
-#[derive(Deserialize, Serialize, Debug)]
+/** Clones are independent, so changing a port's status in a clone does not affect the original */
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PortGraph {
-    port_nodes: HashMap<PortID, PortNode>
+    // serialized in order of ID so that output is the same every time
+    #[serde(serialize_with = "serialize_sorted")]
+    port_nodes: HashMap<PortID, PortNode>,
+    // whether ports may be connected to themselves, e.g. to model internal travel
+    #[serde(default)]
+    allow_self_loops: bool
+}
+
+fn serialize_sorted<S>(port_nodes: &HashMap<PortID, PortNode>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    port_nodes.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
 
 /* Ensure following invariants: */
@@ -31,7 +43,61 @@ pub struct PortGraph {
 // Every connection exists between nodes that exist in graph
 impl PortGraph {
     pub fn new() -> Self{
-        PortGraph {port_nodes: HashMap::new()}
+        PortGraph {port_nodes: HashMap::new(), allow_self_loops: false}
+    }
+
+    /** Creates graph containing given ports, connected by given directed edges */
+    /** Fails on the first port or edge that cannot be added */
+    pub fn from_edges(ports: Vec<Port>, edges: Vec<(PortID, PortID)>) -> Result<Self, String> {
+        let mut graph = Self::new();
+        for port in ports {
+            graph.add_port(port)?;
+        }
+        for (start, end) in edges {
+            graph.add_directed_connection(start, end)?;
+        }
+        Ok(graph)
+    }
+
+    /** Checks that every connection leads to a port in graph */
+    /** Reports first dangling connection found, checking ports in order of ID */
+    pub fn validate(&self) -> Result<(), String> {
+        let mut nodes: Vec<&PortNode> = self.port_nodes.values().collect();
+        nodes.sort_by_key(|node| node.port.id);
+        for node in nodes {
+            if let Some(dangling) = node.dests.iter().find(|dest| !self.in_graph(**dest)) {
+                return Err(format!("Port ID {} is connected to port ID {}, which doesn't exist in graph", node.port.id.0, dangling.0));
+            }
+        }
+        Ok(())
+    }
+
+    /** Creates copy of graph with every connection going the opposite way */
+    /** Ports are cloned, and connections to ports not in graph are dropped */
+    pub fn reverse(&self) -> PortGraph {
+        let mut nodes: Vec<&PortNode> = self.port_nodes.values().collect();
+        nodes.sort_by_key(|node| node.port.id);
+        let mut reversed = PortGraph {port_nodes: HashMap::new(), allow_self_loops: self.allow_self_loops};
+        for node in &nodes {
+            reversed.port_nodes.insert(node.port.id, PortNode::new(node.port.clone()));
+        }
+        for node in &nodes {
+            for dest in &node.dests {
+                if let Some(dest_node) = reversed.get_mut_node(*dest) {
+                    dest_node.dests.push(node.port.id);
+                }
+            }
+        }
+        reversed
+    }
+
+    /** Sets whether ports may be connected to themselves, which is disallowed by default */
+    pub fn set_allow_self_loops(&mut self, allow: bool) {
+        self.allow_self_loops = allow;
+    }
+
+    pub fn allows_self_loops(&self) -> bool {
+        self.allow_self_loops
     }
 
     /** Returns references to all ports in graph */
@@ -39,6 +105,16 @@ impl PortGraph {
         self.port_nodes.values().map(|node| &node.port).collect()
     }
 
+    /** Iterates over all ports in graph without allocating */
+    pub fn iter_ports(&self) -> impl Iterator<Item = &Port> {
+        self.port_nodes.values().map(|node| &node.port)
+    }
+
+    /** Number of ports in graph */
+    pub fn port_count(&self) -> usize {
+        self.port_nodes.len()
+    }
+
     pub fn add_port(&mut self, port: Port) -> Result<(), String> {
         let id = port.id;
         if self.in_graph(id) {
@@ -72,6 +148,11 @@ impl PortGraph {
         }
     }
 
+    /** Not public so that port IDs cannot be changed */
+    pub(crate) fn get_mut_port(&mut self, id: PortID) -> Option<&mut Port> {
+        self.get_mut_node(id).map(|node| &mut node.port)
+    }
+
     // gets possible destination ports of a port in graph, if it exists
     pub fn get_dest_ports(&self, id: PortID) -> Option<Vec<&Port>> {
         // check if port in graph
@@ -101,13 +182,55 @@ impl PortGraph {
                     open_dests.push(dest);
                 }
             }
-            Some(dests)
+            Some(open_dests)
+        }
+    }
+
+    /** Sums capacities of all destination ports of a port, saturating at u32::MAX */
+    /** Returns None if port is not in graph */
+    pub fn total_outbound_capacity(&self, id: PortID) -> Option<u32> {
+        let dests = self.get_dest_ports(id)?;
+        Some(dests.iter().fold(0_u32, |total, dest| total.saturating_add(dest.capacity)))
+    }
+
+    /** Finds ports reachable from start using at most hops connections, excluding start itself */
+    /** Ports are ordered by fewest hops needed to reach them, then by ID, and none are returned if start is not in graph */
+    pub fn reachable_within(&self, start: PortID, hops: usize) -> Vec<PortID> {
+        let mut reached: Vec<PortID> = vec![];
+        if !self.in_graph(start) {
+            return reached;
+        }
+        let mut visited: HashSet<PortID> = HashSet::from([start]);
+        let mut frontier: Vec<PortID> = vec![start];
+        for _ in 0..hops {
+            let mut next_frontier: Vec<PortID> = frontier.iter()
+                .filter_map(|id| self.get_node(*id))
+                .flat_map(|node| node.dests.iter().copied())
+                .filter(|dest| self.in_graph(*dest) && visited.insert(*dest))
+                .collect();
+            if next_frontier.is_empty() {
+                break;
+            }
+            next_frontier.sort();
+            reached.extend(next_frontier.iter().copied());
+            frontier = next_frontier;
         }
+        reached
+    }
+
+    /** Checks whether a connection exists from start to end */
+    pub fn has_connection(&self, start: PortID, end: PortID) -> bool {
+        self.get_node(start).is_some_and(|node| node.dests.contains(&end))
+    }
+
+    /** Checks whether ports are connected in both directions, e.g. by an undirected connection */
+    pub fn is_bidirectional(&self, port1: PortID, port2: PortID) -> bool {
+        self.has_connection(port1, port2) && self.has_connection(port2, port1)
     }
 
     pub fn add_directed_connection(&mut self, start: PortID, end: PortID) -> Result<(), String> {
-        // make sure both IDs are different
-        if start == end {
+        // make sure both IDs are different, unless self loops allowed
+        if start == end && !self.allow_self_loops {
             Err(format!("Cannot connect PortIDs {} and {}, must be different", start.0, end.0))
         }
         // check if both IDs exist in graph
@@ -126,10 +249,14 @@ impl PortGraph {
     }
 
     pub fn add_undirected_connection(&mut self, port1: PortID, port2: PortID) -> Result<(), String> {
-        // make sure both IDs are different
-        if port1 == port2 {
+        // make sure both IDs are different, unless self loops allowed
+        if port1 == port2 && !self.allow_self_loops {
             Err(format!("Cannot connect PortIDs {} and {}, must be different", port1.0, port2.0))
         }
+        // an undirected self loop is a single connection
+        else if port1 == port2 {
+            self.add_directed_connection(port1, port2)
+        }
         // check if both IDs exist in graph
         else if !self.in_graph(port1) || !self.in_graph(port2) {
             Err(format!("At least one Port ID of {} or {} doesn't exist in graph", port1.0, port2.0).to_owned())
@@ -172,13 +299,13 @@ mod tests {
         let mut american_ports: Vec<Port> = vec![];
         let mut europe_ports: Vec<Port> = vec![];
         
-        let amer1 = america.add_port(PortID::new(0), 150, Point2D::default());
-        let amer2 = america.add_port(PortID::new(1), 170, Point2D::default());
+        let amer1 = america.add_port(PortID::new(0), 150, Point2D::default()).unwrap();
+        let amer2 = america.add_port(PortID::new(1), 170, Point2D::default()).unwrap();
 
-        let eu1 = europe.add_port(PortID::new(2), 190, Point2D::default());
-        let eu2 = europe.add_port(PortID::new(3), 300, Point2D::default());
-        let eu3 = europe.add_port(PortID::new(4), 500, Point2D::default());
-        let eu4 = europe.add_port(PortID::new(5), 800, Point2D::default());
+        let eu1 = europe.add_port(PortID::new(2), 190, Point2D::default()).unwrap();
+        let eu2 = europe.add_port(PortID::new(3), 300, Point2D::default()).unwrap();
+        let eu3 = europe.add_port(PortID::new(4), 500, Point2D::default()).unwrap();
+        let eu4 = europe.add_port(PortID::new(5), 800, Point2D::default()).unwrap();
 
         american_ports.push(amer1);
         american_ports.push(amer2);
@@ -266,4 +393,171 @@ mod tests {
         assert_eq!(graph.get_dest_ports(PortID(3)), Some(vec![graph.get_port(PortID(0)).unwrap(), graph.get_port(PortID(1)).unwrap()]));
 
     }
+
+    #[test]
+    fn graph_iter_ports() {
+        let mut america = Region::new("America".to_owned(), Population::new_healthy(3000));
+        let mut graph = PortGraph::new();
+        for id in 0..5 {
+            graph.add_port(america.add_port(PortID::new(id), 100, Point2D::default()).unwrap()).unwrap();
+        }
+
+        let mut iterated_ids: Vec<u32> = graph.iter_ports().map(|port| port.id.0).collect();
+        let mut collected_ids: Vec<u32> = graph.get_ports().iter().map(|port| port.id.0).collect();
+        iterated_ids.sort();
+        collected_ids.sort();
+        assert_eq!(iterated_ids, collected_ids);
+        assert_eq!(iterated_ids, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn graph_self_loops() {
+        let mut america = Region::new("America".to_owned(), Population::new_healthy(3000));
+        let mut graph = PortGraph::new();
+        graph.add_port(america.add_port(PortID::new(0), 100, Point2D::default()).unwrap()).unwrap();
+        graph.add_port(america.add_port(PortID::new(1), 100, Point2D::default()).unwrap()).unwrap();
+
+        // self loops rejected by default
+        assert!(!graph.allows_self_loops());
+        assert!(graph.add_directed_connection(PortID(0), PortID(0)).is_err());
+        assert!(graph.add_undirected_connection(PortID(1), PortID(1)).is_err());
+        assert_eq!(graph.get_dest_ports(PortID(0)), Some(vec![]));
+
+        graph.set_allow_self_loops(true);
+        assert!(graph.add_directed_connection(PortID(0), PortID(0)).is_ok());
+        assert!(graph.add_undirected_connection(PortID(1), PortID(1)).is_ok());
+        assert_eq!(graph.get_dest_ports(PortID(0)), Some(vec![graph.get_port(PortID(0)).unwrap()]));
+        assert_eq!(graph.get_dest_ports(PortID(1)), Some(vec![graph.get_port(PortID(1)).unwrap()]));
+
+        // still cannot add same connection twice
+        assert!(graph.add_directed_connection(PortID(0), PortID(0)).is_err());
+        assert!(graph.add_undirected_connection(PortID(1), PortID(1)).is_err());
+    }
+
+    #[test]
+    fn graph_from_edges() {
+        let mut america = Region::new("America".to_owned(), Population::new_healthy(3000));
+        let ports: Vec<Port> = (0..3).map(|id| america.add_port(PortID::new(id), 100, Point2D::default()).unwrap()).collect();
+
+        let graph = PortGraph::from_edges(ports.clone(), vec![(PortID(0), PortID(1)), (PortID(1), PortID(2)), (PortID(2), PortID(0))]).unwrap();
+        assert_eq!(graph.get_dest_ports(PortID(0)), Some(vec![graph.get_port(PortID(1)).unwrap()]));
+        assert_eq!(graph.get_dest_ports(PortID(1)), Some(vec![graph.get_port(PortID(2)).unwrap()]));
+        assert_eq!(graph.get_dest_ports(PortID(2)), Some(vec![graph.get_port(PortID(0)).unwrap()]));
+
+        // bad edges and duplicate ports surface errors
+        assert!(PortGraph::from_edges(ports.clone(), vec![(PortID(0), PortID(5))]).is_err());
+        assert!(PortGraph::from_edges(ports.clone(), vec![(PortID(0), PortID(1)), (PortID(0), PortID(1))]).is_err());
+        assert!(PortGraph::from_edges(vec![ports[0].clone(), ports[0].clone()], vec![]).is_err());
+    }
+
+    #[test]
+    fn graph_is_bidirectional() {
+        let mut america = Region::new("America".to_owned(), Population::new_healthy(3000));
+        let ports: Vec<Port> = (0..3).map(|id| america.add_port(PortID::new(id), 100, Point2D::default()).unwrap()).collect();
+        let mut graph = PortGraph::from_edges(ports, vec![(PortID(0), PortID(1))]).unwrap();
+        graph.add_undirected_connection(PortID(1), PortID(2)).unwrap();
+
+        // directed only
+        assert!(graph.has_connection(PortID(0), PortID(1)));
+        assert!(!graph.has_connection(PortID(1), PortID(0)));
+        assert!(!graph.is_bidirectional(PortID(0), PortID(1)));
+        assert!(!graph.is_bidirectional(PortID(1), PortID(0)));
+
+        // undirected pair
+        assert!(graph.is_bidirectional(PortID(1), PortID(2)));
+        assert!(graph.is_bidirectional(PortID(2), PortID(1)));
+
+        // unconnected or nonexistent ports
+        assert!(!graph.is_bidirectional(PortID(0), PortID(2)));
+        assert!(!graph.is_bidirectional(PortID(0), PortID(50)));
+    }
+
+    #[test]
+    fn graph_validate() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let port0 = country.add_port(PortID(0), 100, Point2D::default()).unwrap();
+        let port1 = country.add_port(PortID(1), 100, Point2D::default()).unwrap();
+        let mut graph = PortGraph::from_edges(vec![port0, port1], vec![(PortID(0), PortID(1)), (PortID(1), PortID(0))]).unwrap();
+        assert!(graph.validate().is_ok());
+        assert!(PortGraph::new().validate().is_ok());
+
+        // connection to port that was never added
+        graph.port_nodes.get_mut(&PortID(1)).unwrap().dests.push(PortID(7));
+        let err = graph.validate().unwrap_err();
+        assert!(err.contains("Port ID 1") && err.contains("port ID 7"), "{}", err);
+    }
+
+    #[test]
+    fn graph_serialization_stable() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let ports: Vec<Port> = (0..20).rev().map(|id| country.add_port(PortID(id), 100, Point2D::default()).unwrap()).collect();
+        let build_graph = || PortGraph::from_edges(ports.clone(), vec![(PortID(3), PortID(7)), (PortID(12), PortID(0))]).unwrap();
+        let json = serde_json::to_string(&build_graph()).unwrap();
+        assert_eq!(json, serde_json::to_string(&build_graph()).unwrap());
+
+        // deserialized graph has its own hash map ordering
+        let deserialized: PortGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        // ports ordered by ID
+        assert!(json.find("\"2\":").unwrap() < json.find("\"10\":").unwrap());
+    }
+
+    #[test]
+    fn total_outbound_capacity() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let ports = vec![
+            country.add_port(PortID(0), 10, Point2D::default()).unwrap(),
+            country.add_port(PortID(1), 20, Point2D::default()).unwrap(),
+            country.add_port(PortID(2), 300, Point2D::default()).unwrap(),
+            country.add_port(PortID(3), u32::MAX, Point2D::default()).unwrap(),
+        ];
+        let graph = PortGraph::from_edges(ports, vec![(PortID(0), PortID(1)), (PortID(0), PortID(2)), (PortID(1), PortID(2)), (PortID(1), PortID(3))]).unwrap();
+        assert_eq!(graph.total_outbound_capacity(PortID(0)), Some(320));
+        assert_eq!(graph.total_outbound_capacity(PortID(1)), Some(u32::MAX));
+        assert_eq!(graph.total_outbound_capacity(PortID(2)), Some(0));
+        assert_eq!(graph.total_outbound_capacity(PortID(9)), None);
+    }
+
+    #[test]
+    fn graph_reverse() {
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let ports: Vec<Port> = (0..4).map(|id| country.add_port(PortID(id), 100, Point2D::new(id as f64, 0.0)).unwrap()).collect();
+        let graph = PortGraph::from_edges(ports, vec![(PortID(0), PortID(1)), (PortID(0), PortID(2)), (PortID(2), PortID(1)), (PortID(3), PortID(0)), (PortID(1), PortID(3))]).unwrap();
+        let edges = |graph: &PortGraph| {
+            let mut edges: Vec<(PortID, PortID)> = graph.port_nodes.values().flat_map(|node| node.dests.iter().map(|dest| (node.port.id, *dest))).collect();
+            edges.sort();
+            edges
+        };
+
+        let reversed = graph.reverse();
+        assert_eq!(reversed.port_count(), 4);
+        assert!(reversed.has_connection(PortID(1), PortID(0)) && !reversed.has_connection(PortID(0), PortID(1)));
+        assert_eq!(reversed.get_dest_ports(PortID(1)).unwrap().len(), 2);
+        assert_eq!(reversed.get_port(PortID(3)), graph.get_port(PortID(3)));
+        assert!(reversed.validate().is_ok());
+
+        let twice_reversed = reversed.reverse();
+        assert_eq!(edges(&twice_reversed), edges(&graph));
+        for port in graph.iter_ports() {
+            assert_eq!(twice_reversed.get_port(port.id), Some(port));
+        }
+    }
+
+    #[test]
+    fn reachable_within() {
+        // chain 0 -> 1 -> 2 -> 3 -> 4, with a shortcut 0 -> 2 and a loop back 4 -> 0
+        let mut country = Region::new("Atlantis".to_owned(), Population::new_healthy(100));
+        let ports: Vec<Port> = (0..6).map(|id| country.add_port(PortID(id), 100, Point2D::default()).unwrap()).collect();
+        let graph = PortGraph::from_edges(ports, vec![(PortID(0), PortID(1)), (PortID(1), PortID(2)), (PortID(2), PortID(3)), (PortID(3), PortID(4)), (PortID(0), PortID(2)), (PortID(4), PortID(0))]).unwrap();
+
+        assert!(graph.reachable_within(PortID(0), 0).is_empty());
+        assert_eq!(graph.reachable_within(PortID(0), 1), vec![PortID(1), PortID(2)]);
+        assert_eq!(graph.reachable_within(PortID(0), 2), vec![PortID(1), PortID(2), PortID(3)]);
+        assert_eq!(graph.reachable_within(PortID(0), 3), vec![PortID(1), PortID(2), PortID(3), PortID(4)]);
+        assert_eq!(graph.reachable_within(PortID(0), 100), vec![PortID(1), PortID(2), PortID(3), PortID(4)]);
+        assert_eq!(graph.reachable_within(PortID(3), 2), vec![PortID(4), PortID(0)]);
+        // unconnected and missing ports reach nothing
+        assert!(graph.reachable_within(PortID(5), 3).is_empty());
+        assert!(graph.reachable_within(PortID(9), 3).is_empty());
+    }
 }
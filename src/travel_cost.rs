@@ -0,0 +1,73 @@
+// Responsible for calculating how long travel between ports takes
+
+use crate::region::Port;
+
+/** Determines how many ticks it takes to travel from one port to another */
+/** Implementations should fail if the travel time cannot be calculated, e.g. a port has an invalid position */
+pub trait TravelCost {
+    fn ticks(&self, start: &Port, end: &Port) -> Result<u32, String>;
+}
+
+/// Travel takes as many ticks as the straight line distance between ports, rounded down
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EuclideanCost;
+
+impl TravelCost for EuclideanCost {
+    fn ticks(&self, start: &Port, end: &Port) -> Result<u32, String> {
+        start.pos.try_distance(&end.pos).map(|distance| distance as u32)
+    }
+}
+
+/// Travel between any two ports takes the same amount of ticks
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FlatCost {
+    pub ticks: u32
+}
+
+impl FlatCost {
+    pub fn new(ticks: u32) -> Self {
+        Self {ticks}
+    }
+}
+
+impl TravelCost for FlatCost {
+    fn ticks(&self, _start: &Port, _end: &Port) -> Result<u32, String> {
+        Ok(self.ticks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{point::Point2D, population_types::population::Population, region::{PortID, Region}};
+
+    use super::{EuclideanCost, FlatCost, TravelCost};
+
+    #[test]
+    fn euclidean_cost() {
+        let mut region: Region = Region::new("Brazil".to_owned(), Population::new_healthy(100));
+        let port1 = region.add_port(PortID(0), 10, Point2D::new(0.0, 0.0)).unwrap();
+        let port2 = region.add_port(PortID(1), 10, Point2D::new(3.0, 4.0)).unwrap();
+        let port3 = region.add_port(PortID(2), 10, Point2D::new(1.5, 2.5)).unwrap();
+        let mut broken_port = region.add_port(PortID(3), 10, Point2D::default()).unwrap();
+        broken_port.pos = Point2D::new(f64::NAN, 0.0);
+
+        assert_eq!(EuclideanCost.ticks(&port1, &port2), Ok(5));
+        assert_eq!(EuclideanCost.ticks(&port2, &port1), Ok(5));
+        assert_eq!(EuclideanCost.ticks(&port1, &port1), Ok(0));
+        // rounds down
+        assert_eq!(EuclideanCost.ticks(&port1, &port3), Ok(2));
+        assert!(EuclideanCost.ticks(&port1, &broken_port).is_err());
+    }
+
+    #[test]
+    fn flat_cost() {
+        let mut region: Region = Region::new("Brazil".to_owned(), Population::new_healthy(100));
+        let port1 = region.add_port(PortID(0), 10, Point2D::new(0.0, 0.0)).unwrap();
+        let port2 = region.add_port(PortID(1), 10, Point2D::new(300.0, 400.0)).unwrap();
+
+        let cost = FlatCost::new(7);
+        assert_eq!(cost.ticks(&port1, &port2), Ok(7));
+        assert_eq!(cost.ticks(&port2, &port1), Ok(7));
+        assert_eq!(cost.ticks(&port1, &port1), Ok(7));
+    }
+}